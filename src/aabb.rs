@@ -0,0 +1,161 @@
+use crate::prelude::*;
+
+/// Axis-aligned bounding box, stored as one interval per axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    /// Minimum extent of any axis, so that flat primitives still produce a box the slab test
+    /// can hit.
+    const MIN_EXTENT: f64 = 0.0001;
+
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        let mut bbox = Self { x, y, z };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    pub fn empty() -> Self {
+        Self { x: Interval::empty(), y: Interval::empty(), z: Interval::empty() }
+    }
+
+    pub fn universe() -> Self {
+        Self { x: Interval::universe(), y: Interval::universe(), z: Interval::universe() }
+    }
+
+    /// Treats the two points a and b as extrema for the bounding box, so we don't require a
+    /// particular minimum/maximum coordinate order.
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::new(
+            Interval::new(f64::min(a.x, b.x), f64::max(a.x, b.x)),
+            Interval::new(f64::min(a.y, b.y), f64::max(a.y, b.y)),
+            Interval::new(f64::min(a.z, b.z), f64::max(a.z, b.z)),
+        )
+    }
+
+    /// Creates the smallest box enclosing both input boxes.
+    pub fn from_boxes(a: &Aabb, b: &Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(a.x, b.x),
+            y: Interval::enclosing(a.y, b.y),
+            z: Interval::enclosing(a.z, b.z),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.len() < 0.0 || self.y.len() < 0.0 || self.z.len() < 0.0
+    }
+
+    pub fn axis_interval(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    /// Slab test: clips the ray interval against each pair of axis planes in turn and reports
+    /// whether anything is left over.
+    pub fn hit(&self, r: &Ray, mut ray_t: Interval) -> bool {
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let adinv = 1.0 / r.direction[axis];
+
+            let t0 = (ax.min - r.origin[axis]) * adinv;
+            let t1 = (ax.max - r.origin[axis]) * adinv;
+
+            if t0 < t1 {
+                if t0 > ray_t.min { ray_t.min = t0; }
+                if t1 < ray_t.max { ray_t.max = t1; }
+            } else {
+                if t1 > ray_t.min { ray_t.min = t1; }
+                if t0 < ray_t.max { ray_t.max = t0; }
+            }
+
+            if ray_t.max <= ray_t.min { return false; }
+        }
+        true
+    }
+
+    /// Returns the index of the longest axis of the bounding box.
+    pub fn longest_axis(&self) -> usize {
+        if self.x.len() > self.y.len() {
+            if self.x.len() > self.z.len() { 0 } else { 2 }
+        } else if self.y.len() > self.z.len() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    /// Vector from the minimum corner to the maximum corner.
+    pub fn diagonal(&self) -> Vec3 {
+        Vec3::new(self.x.len(), self.y.len(), self.z.len())
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() { return 0.0; }
+        let d = self.diagonal();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Adjusts the box so that no side is narrower than some delta, padding if necessary.
+    fn pad_to_minimums(&mut self) {
+        for ax in [&mut self.x, &mut self.y, &mut self.z] {
+            if ax.len() >= 0.0 && ax.len() < Self::MIN_EXTENT {
+                *ax = ax.expand(Self::MIN_EXTENT);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_boxes_are_padded_around_their_plane() {
+        let bbox = Aabb::from_points(Point3::new(0.0, 2.0, 0.0), Point3::new(1.0, 2.0, 1.0));
+        assert!((bbox.y.len() - Aabb::MIN_EXTENT).abs() < 1e-12);
+        assert!((bbox.y.min + bbox.y.max - 4.0).abs() < 1e-12);
+        assert_eq!(bbox.x.len(), 1.0);
+
+        let r = Ray::new(Point3::new(0.5, 5.0, 0.5), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(bbox.hit(&r, Interval::new(0.0, f64::INFINITY)));
+    }
+
+    #[test]
+    fn empty_boxes_stay_empty() {
+        assert!(Aabb::empty().is_empty());
+        assert_eq!(Aabb::empty().surface_area(), 0.0);
+        let unit = Aabb::from_points(Point3::zero(), Point3::new(1.0, 2.0, 3.0));
+        let bbox = Aabb::from_boxes(&Aabb::empty(), &unit);
+        assert!(!bbox.is_empty());
+        assert_eq!(bbox.surface_area(), 22.0);
+        assert_eq!(bbox.longest_axis(), 2);
+    }
+
+    #[test]
+    fn slab_test_respects_the_ray_interval() {
+        let bbox = Aabb::from_points(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(bbox.hit(&r, Interval::new(0.0, f64::INFINITY)));
+        assert!(!bbox.hit(&r, Interval::new(0.0, 3.0)));
+        assert!(!bbox.hit(&r, Interval::new(7.0, 10.0)));
+
+        let parallel = Ray::new(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(!bbox.hit(&parallel, Interval::new(0.0, f64::INFINITY)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::scene::{KeyframeDescription, MotionDescription, ObjectDescription};
+use crate::transform::Transform;
+
+/// How an animated transform moves between its keyframes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Straight lines between keyframes, changing speed abruptly at each one.
+    #[default]
+    Linear,
+    /// A Catmull-Rom spline through the keyframes, with smooth changes of speed.
+    Spline,
+}
+
+/// The placement of an object at one point in time. The object is scaled first, then rotated
+/// around the x, y and z axes in that order, then translated. Rotations are in degrees and are
+/// interpolated as angles, so a keyframe may turn an object several full times.
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translate: Vec3,
+    pub rotate: Vec3,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    /// A keyframe at `time` that leaves the object where it is.
+    pub fn at(time: f64) -> Self {
+        Self {
+            time,
+            translate: Vec3::zero(),
+            rotate: Vec3::zero(),
+            scale: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn translate(mut self, offset: Vec3) -> Self {
+        self.translate = offset;
+        self
+    }
+
+    pub fn rotate(mut self, degrees: Vec3) -> Self {
+        self.rotate = degrees;
+        self
+    }
+
+    pub fn scale(mut self, factors: Vec3) -> Self {
+        self.scale = factors;
+        self
+    }
+
+    fn components(&self) -> [f64; 9] {
+        let (t, r, s) = (self.translate, self.rotate, self.scale);
+        [t.x, t.y, t.z, r.x, r.y, r.z, s.x, s.y, s.z]
+    }
+
+    fn from_components(time: f64, c: [f64; 9]) -> Self {
+        Self {
+            time,
+            translate: Vec3::new(c[0], c[1], c[2]),
+            rotate: Vec3::new(c[3], c[4], c[5]),
+            scale: Vec3::new(c[6], c[7], c[8]),
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::scale(self.scale)
+            .then(Transform::rotate_x(self.rotate.x))
+            .then(Transform::rotate_y(self.rotate.y))
+            .then(Transform::rotate_z(self.rotate.z))
+            .then(Transform::translate(self.translate))
+    }
+}
+
+/// A transform that changes over time, given by keyframes. Before the first keyframe and after
+/// the last the transform holds still.
+#[derive(Debug, Clone)]
+pub struct AnimatedTransform {
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+}
+
+impl AnimatedTransform {
+    /// Sorts `keyframes` by time. There must be at least one.
+    pub fn new(mut keyframes: Vec<Keyframe>, interpolation: Interpolation) -> Self {
+        assert!(!keyframes.is_empty(), "an animated transform needs a keyframe");
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { keyframes, interpolation }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// The keyframe values at `time`.
+    pub fn keyframe_at(&self, time: f64) -> Keyframe {
+        let keys = &self.keyframes;
+        let last = keys.len() - 1;
+        if time <= keys[0].time { return Keyframe { time, ..keys[0] }; }
+        if time >= keys[last].time { return Keyframe { time, ..keys[last] }; }
+
+        let k = keys.partition_point(|key| key.time <= time) - 1;
+        let (p0, p1) = (keys[k].components(), keys[k + 1].components());
+        let h = keys[k + 1].time - keys[k].time;
+        let s = (time - keys[k].time) / h;
+
+        let values = match self.interpolation {
+            Interpolation::Linear => std::array::from_fn(|i| p0[i] + s * (p1[i] - p0[i])),
+            Interpolation::Spline => {
+                // Cubic Hermite basis, with Catmull-Rom tangents scaled to the segment.
+                let (m0, m1) = (self.tangent(k), self.tangent(k + 1));
+                let (s2, s3) = (s * s, s * s * s);
+                let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+                let h10 = s3 - 2.0 * s2 + s;
+                let h01 = -2.0 * s3 + 3.0 * s2;
+                let h11 = s3 - s2;
+                std::array::from_fn(|i| {
+                    h00 * p0[i] + h10 * h * m0[i] + h01 * p1[i] + h11 * h * m1[i]
+                })
+            },
+        };
+        Keyframe::from_components(time, values)
+    }
+
+    /// The rate of change at keyframe `k`, from its neighbors.
+    fn tangent(&self, k: usize) -> [f64; 9] {
+        let keys = &self.keyframes;
+        let (before, after) = (k.saturating_sub(1), (k + 1).min(keys.len() - 1));
+        let (a, b) = (keys[before].components(), keys[after].components());
+        let dt = keys[after].time - keys[before].time;
+        std::array::from_fn(|i| if dt > 0.0 { (b[i] - a[i]) / dt } else { 0.0 })
+    }
+
+    pub fn at(&self, time: f64) -> Transform {
+        self.keyframe_at(time).transform()
+    }
+
+    /// A box around `bbox` wherever the animation takes it, found by sampling each segment
+    /// densely. While the rotation changes, the box's bounding sphere is tracked instead of its
+    /// corners, since a turning box can bulge past the corners of the samples. Each sample is
+    /// padded by how far the box can move in half a step, so nothing escapes between samples.
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        const STEPS: usize = 64;
+
+        if bbox.is_empty() { return *bbox; }
+        let rotating = self.keyframes.iter()
+            .any(|key| (key.rotate - self.keyframes[0].rotate).len_squared() > 0.0);
+        let center = bbox.centroid();
+        let radius = 0.5 * bbox.diagonal().len();
+        // No point of the box is farther than this from the object's origin.
+        let reach = center.len() + radius;
+
+        let sample = |time: f64, padding: f64| {
+            let key = self.keyframe_at(time);
+            let moved = if rotating {
+                let scale = key.scale.x.abs().max(key.scale.y.abs()).max(key.scale.z.abs());
+                let c = key.transform().point(center);
+                let r = Vec3::new(1.0, 1.0, 1.0) * (radius * scale);
+                Aabb::from_points(c - r, c + r)
+            } else {
+                key.transform().bounding_box(bbox)
+            };
+            let delta = 2.0 * padding;
+            Aabb::new(moved.x.expand(delta), moved.y.expand(delta), moved.z.expand(delta))
+        };
+
+        let mut result = sample(self.keyframes[0].time, 0.0);
+        for k in 0..self.keyframes.len() - 1 {
+            let (start, end) = (self.keyframes[k].time, self.keyframes[k + 1].time);
+            let step = (end - start) / STEPS as f64;
+            let padding = 0.5 * step * self.max_speed(k, reach);
+            for i in 0..=STEPS {
+                result = Aabb::from_boxes(&result, &sample(start + step * i as f64, padding));
+            }
+        }
+        result
+    }
+
+    /// An upper bound on how fast any point within `reach` of the object's origin moves
+    /// between keyframes `k` and `k + 1`.
+    fn max_speed(&self, k: usize, reach: f64) -> f64 {
+        let keys = &self.keyframes;
+        let h = keys[k + 1].time - keys[k].time;
+        if h <= 0.0 { return 0.0; }
+        let (p0, p1) = (keys[k].components(), keys[k + 1].components());
+
+        // Bounds on the rate of change of each component. The derivatives of the Hermite basis
+        // peak at 1.5 for the end points and 1 for the tangents.
+        let rates: [f64; 9] = match self.interpolation {
+            Interpolation::Linear => std::array::from_fn(|i| (p1[i] - p0[i]).abs() / h),
+            Interpolation::Spline => {
+                let (m0, m1) = (self.tangent(k), self.tangent(k + 1));
+                std::array::from_fn(|i| {
+                    1.5 * (p1[i] - p0[i]).abs() / h + m0[i].abs() + m1[i].abs()
+                })
+            },
+        };
+        let translating = Vec3::new(rates[0], rates[1], rates[2]).len();
+        let turning = (rates[3] + rates[4] + rates[5]).to_radians();
+        let scaling = Vec3::new(rates[6], rates[7], rates[8]).len();
+        let largest_scale = (6..9).map(|i| p0[i].abs() + h * rates[i]).fold(0.0, f64::max);
+
+        translating + (turning * largest_scale + scaling) * reach
+    }
+}
+
+impl From<&AnimatedTransform> for MotionDescription {
+    fn from(motion: &AnimatedTransform) -> Self {
+        Self {
+            interpolation: motion.interpolation,
+            keyframes: motion.keyframes.iter()
+                .map(|key| KeyframeDescription {
+                    time: key.time,
+                    translate: key.translate.into(),
+                    rotate: key.rotate.into(),
+                    scale: key.scale.into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An object moved by an animated transform. Each ray sees the object where it is at the ray's
+/// time. Animated objects can't be sampled as lights, since where they are depends on a time
+/// that light sampling isn't given.
+pub struct Animated<H: Hittable> {
+    object: H,
+    motion: AnimatedTransform,
+    bbox: Aabb,
+}
+
+impl<H: Hittable> Animated<H> {
+    pub fn new(object: H, motion: AnimatedTransform) -> Self {
+        let bbox = motion.bounding_box(&object.bounding_box());
+        Self { object, motion, bbox }
+    }
+}
+
+impl<H: Hittable> Hittable for Animated<H> {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.motion.at(r.time).hit(&self.object, r, ray_t)
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.motion.at(r.time).hit_surface(&self.object, r, ray_t)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        self.object.transmittance(&self.motion.at(r.time).inverse_ray(r), ray_t)
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        self.motion.at(r.time).hit_sampled(&self.object, r, ray_t, sampler)
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        let local = self.motion.at(r.time).inverse_ray(r);
+        self.object.transmittance_sampled(&local, ray_t, sampler)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let object = scene.capture_one(|scene| self.object.describe(scene))?;
+        scene.push_object(ObjectDescription::Animated {
+            motion: MotionDescription::from(&self.motion),
+            object: Box::new(object),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the corners of `bbox` stay inside the animated bounding box throughout.
+    fn assert_bounded(motion: &AnimatedTransform, bbox: &Aabb) {
+        let bounds = motion.bounding_box(bbox);
+        for step in 0..=10_000 {
+            let time = -0.25 + 1.5 * step as f64 / 10_000.0;
+            let transform = motion.at(time);
+            for corner in 0..8 {
+                let pick = |axis: usize, i: &Interval| if corner & (1 << axis) == 0 { i.min } else { i.max };
+                let p = transform.point(Point3::new(pick(0, &bbox.x), pick(1, &bbox.y), pick(2, &bbox.z)));
+                assert!(
+                    bounds.x.contains(p.x) && bounds.y.contains(p.y) && bounds.z.contains(p.z),
+                    "{p:?} escapes {bounds:?} at time {time}",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keyframes_hold_still_outside_their_range() {
+        let motion = AnimatedTransform::new(vec![
+            Keyframe::at(1.0).translate(Vec3::new(2.0, 0.0, 0.0)),
+            Keyframe::at(0.0),
+        ], Interpolation::Linear);
+        assert_eq!(motion.keyframe_at(-1.0).translate.x, 0.0);
+        assert_eq!(motion.keyframe_at(0.25).translate.x, 0.5);
+        assert_eq!(motion.keyframe_at(3.0).translate.x, 2.0);
+    }
+
+    #[test]
+    fn splines_pass_through_their_keyframes() {
+        let motion = AnimatedTransform::new(vec![
+            Keyframe::at(0.0),
+            Keyframe::at(0.5).translate(Vec3::new(1.0, 0.0, 0.0)),
+            Keyframe::at(1.0).translate(Vec3::new(0.0, 3.0, 0.0)),
+        ], Interpolation::Spline);
+        let middle = motion.keyframe_at(0.5).translate;
+        assert!((middle - Vec3::new(1.0, 0.0, 0.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn bounds_cover_a_fast_spin_between_samples() {
+        let blade = Aabb::from_points(Point3::new(2.0, -0.1, -0.1), Point3::new(3.0, 0.1, 0.1));
+        let motion = AnimatedTransform::new(vec![
+            Keyframe::at(0.0),
+            Keyframe::at(1.0).rotate(Vec3::new(0.0, 0.0, 7200.0)).translate(Vec3::new(1.0, 0.0, 0.0)),
+        ], Interpolation::Linear);
+        assert_bounded(&motion, &blade);
+    }
+
+    #[test]
+    fn bounds_cover_spline_overshoot() {
+        let cube = Aabb::from_points(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5));
+        let motion = AnimatedTransform::new(vec![
+            Keyframe::at(0.0),
+            Keyframe::at(0.1).translate(Vec3::new(4.0, 0.0, 0.0)).scale(Vec3::new(2.0, 1.0, 1.0)),
+            Keyframe::at(0.2).translate(Vec3::new(0.0, 0.0, -4.0)).rotate(Vec3::new(90.0, 0.0, 0.0)),
+            Keyframe::at(1.0).translate(Vec3::new(-3.0, 1.0, 0.0)),
+        ], Interpolation::Spline);
+        assert_bounded(&motion, &cube);
+    }
+}
\ No newline at end of file
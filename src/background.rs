@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use crate::prelude::*;
+
+/// Anything that can supply the radiance seen along a ray that escapes the scene. Implemented
+/// for closures, so `Background::Custom(Arc::new(|r: &Ray| ...))` works directly.
+pub trait Environment: Send + Sync {
+    fn color(&self, r: &Ray) -> Color;
+}
+
+impl<F> Environment for F
+where
+    F: Fn(&Ray) -> Color + Send + Sync,
+{
+    fn color(&self, r: &Ray) -> Color {
+        self(r)
+    }
+}
+
+/// What the camera sees when a ray misses every object.
+#[derive(Clone)]
+pub enum Background {
+    /// The same color in every direction.
+    Solid(Color),
+    /// Blends linearly from `bottom` (straight down) to `top` (straight up).
+    Gradient { bottom: Color, top: Color },
+    /// A user-supplied environment.
+    Custom(Arc<dyn Environment>),
+}
+
+impl Background {
+    /// The white-to-blue sky used throughout the book.
+    pub fn sky() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+
+    pub fn color(&self, r: &Ray) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = r.direction.unit_vector();
+                let a = 0.5 * (unit_direction.y + 1.0);
+                (1.0 - a) * *bottom + a * *top
+            },
+            Background::Custom(environment) => environment.color(r),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::sky()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn looking(direction: Vec3) -> Ray {
+        Ray::new(Point3::zero(), direction, 0.0)
+    }
+
+    #[test]
+    fn gradients_run_from_bottom_to_top() {
+        let background = Background::Gradient { bottom: Color::zero(), top: Color::new(1.0, 1.0, 1.0) };
+        assert_eq!(background.color(&looking(Vec3::new(0.0, -2.0, 0.0))).x, 0.0);
+        assert_eq!(background.color(&looking(Vec3::new(3.0, 0.0, 0.0))).x, 0.5);
+        assert_eq!(background.color(&looking(Vec3::new(0.0, 5.0, 0.0))).x, 1.0);
+    }
+
+    #[test]
+    fn solid_and_custom_backgrounds() {
+        let solid = Background::Solid(Color::new(0.2, 0.3, 0.4));
+        assert_eq!(solid.color(&looking(Vec3::new(1.0, 2.0, 3.0))).z, 0.4);
+
+        let custom = Background::Custom(Arc::new(|r: &Ray| Color::new(r.direction.x, 0.0, 0.0)));
+        assert_eq!(custom.color(&looking(Vec3::new(0.7, 0.0, 0.0))).x, 0.7);
+    }
+}
\ No newline at end of file
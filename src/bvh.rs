@@ -0,0 +1,265 @@
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+
+/// How a BVH node divides its primitives between its two children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitMethod {
+    /// Splits at the spatial midpoint of the longest axis of the primitive centroids. Cheap to
+    /// build, but the resulting tree can be noticeably slower to trace.
+    Midpoint,
+    /// Sweeps every axis looking for the partition with the lowest surface area heuristic cost.
+    /// Slower to build, faster to trace.
+    Sah,
+}
+
+struct BuildItem {
+    bbox: Aabb,
+    centroid: Point3,
+    object: Box<dyn Hittable>,
+}
+
+pub struct BvhNode {
+    left: Box<dyn Hittable>,
+    right: Option<Box<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList, method: SplitMethod) -> Self {
+        let items = list.objects.into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                BuildItem { bbox, centroid: bbox.centroid(), object }
+            })
+            .collect();
+        Self::build(items, method)
+    }
+
+    fn build(mut items: Vec<BuildItem>, method: SplitMethod) -> Self {
+        let bbox = items.iter()
+            .fold(Aabb::empty(), |acc, item| Aabb::from_boxes(&acc, &item.bbox));
+
+        match items.len() {
+            0 => Self { left: Box::new(HittableList::new()), right: None, bbox },
+            1 => {
+                let left = items.pop().unwrap().object;
+                Self { left, right: None, bbox }
+            },
+            2 => {
+                let right = items.pop().unwrap().object;
+                let left = items.pop().unwrap().object;
+                Self { left, right: Some(right), bbox }
+            },
+            _ => {
+                let right_items = match method {
+                    SplitMethod::Midpoint => Self::split_midpoint(&mut items),
+                    SplitMethod::Sah => Self::split_sah(&mut items),
+                };
+                Self {
+                    left: Self::subtree(items, method),
+                    right: Some(Self::subtree(right_items, method)),
+                    bbox,
+                }
+            },
+        }
+    }
+
+    fn subtree(mut items: Vec<BuildItem>, method: SplitMethod) -> Box<dyn Hittable> {
+        if items.len() == 1 {
+            items.pop().unwrap().object
+        } else {
+            Box::new(Self::build(items, method))
+        }
+    }
+
+    /// Partitions `items` in place around the centroid midpoint of the longest axis and returns
+    /// the upper half. Falls back to an even split when every centroid lands on one side.
+    fn split_midpoint(items: &mut Vec<BuildItem>) -> Vec<BuildItem> {
+        let centroid_bounds = items.iter().fold(Aabb::empty(), |acc, item| {
+            Aabb::from_boxes(&acc, &Aabb::from_points(item.centroid, item.centroid))
+        });
+        let axis = centroid_bounds.longest_axis();
+        let ax = centroid_bounds.axis_interval(axis);
+        let mid = 0.5 * (ax.min + ax.max);
+
+        let mut split = 0;
+        for i in 0..items.len() {
+            if items[i].centroid[axis] < mid {
+                items.swap(i, split);
+                split += 1;
+            }
+        }
+
+        if split == 0 || split == items.len() {
+            split = items.len() / 2;
+        }
+        items.split_off(split)
+    }
+
+    /// Finds the split minimizing `area(left) * n_left + area(right) * n_right` over all three
+    /// axes, with primitives ordered by centroid, and returns the upper half.
+    fn split_sah(items: &mut Vec<BuildItem>) -> Vec<BuildItem> {
+        let n = items.len();
+        let mut best_cost = f64::INFINITY;
+        let mut best_axis = 0;
+        let mut best_split = n / 2;
+        let mut right_areas = vec![0.0; n];
+
+        for axis in 0..3 {
+            Self::sort_by_centroid(items, axis);
+
+            let mut right_box = Aabb::empty();
+            for i in (1..n).rev() {
+                right_box = Aabb::from_boxes(&right_box, &items[i].bbox);
+                right_areas[i] = right_box.surface_area();
+            }
+
+            let mut left_box = Aabb::empty();
+            for i in 1..n {
+                left_box = Aabb::from_boxes(&left_box, &items[i - 1].bbox);
+                let cost = left_box.surface_area() * i as f64
+                    + right_areas[i] * (n - i) as f64;
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_axis = axis;
+                    best_split = i;
+                }
+            }
+        }
+
+        Self::sort_by_centroid(items, best_axis);
+        items.split_off(best_split)
+    }
+
+    fn sort_by_centroid(items: &mut [BuildItem], axis: usize) {
+        items.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if !self.bbox.hit(r, ray_t) { return None; }
+
+        let hit_left = self.left.hit(r, ray_t);
+        let Some(right) = &self.right else { return hit_left; };
+
+        let closest = hit_left.as_ref().map_or(ray_t.max, |rec| rec.t);
+        right.hit(r, Interval::new(ray_t.min, closest)).or(hit_left)
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if !self.bbox.hit(r, ray_t) { return None; }
+
+        let hit_left = self.left.hit_surface(r, ray_t);
+        let Some(right) = &self.right else { return hit_left; };
+
+        let closest = hit_left.as_ref().map_or(ray_t.max, |rec| rec.t);
+        right.hit_surface(r, Interval::new(ray_t.min, closest)).or(hit_left)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        if !self.bbox.hit(r, ray_t) { return 1.0; }
+
+        let left = self.left.transmittance(r, ray_t);
+        match &self.right {
+            Some(right) if left > 0.0 => left * right.transmittance(r, ray_t),
+            _ => left,
+        }
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        if !self.bbox.hit(r, ray_t) { return None; }
+
+        let hit_left = self.left.hit_sampled(r, ray_t, sampler);
+        let Some(right) = &self.right else { return hit_left; };
+
+        let closest = hit_left.as_ref().map_or(ray_t.max, |rec| rec.t);
+        right.hit_sampled(r, Interval::new(ray_t.min, closest), sampler).or(hit_left)
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        if !self.bbox.hit(r, ray_t) { return 1.0; }
+
+        let left = self.left.transmittance_sampled(r, ray_t, sampler);
+        match &self.right {
+            Some(right) if left > 0.0 => left * right.transmittance_sampled(r, ray_t, sampler),
+            _ => left,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        self.left.describe(scene)?;
+        match &self.right {
+            Some(right) => right.describe(scene),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::Rng as _;
+
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::rng;
+    use crate::sphere::Sphere;
+
+    fn spheres(count: usize) -> HittableList {
+        let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let mut rng = rng::seeded(1);
+        let mut list = HittableList::new();
+        for _ in 0..count {
+            let center = Point3::new(
+                rng.gen_range(-5.0..5.0),
+                rng.gen_range(-5.0..5.0),
+                rng.gen_range(-5.0..5.0),
+            );
+            let radius = rng.gen_range(0.1..0.8);
+            list.objects.push(Box::new(Sphere::stationary(center, radius, mat.clone())));
+        }
+        list
+    }
+
+    #[test]
+    fn finds_the_same_hits_as_a_plain_list() {
+        let list = spheres(100);
+        let mut rng = rng::seeded(2);
+        for method in [SplitMethod::Midpoint, SplitMethod::Sah] {
+            let bvh = BvhNode::new(spheres(100), method);
+            for _ in 0..500 {
+                let origin = Point3::new(rng.gen_range(-8.0..8.0), rng.gen_range(-8.0..8.0), 10.0);
+                let direction = Vec3::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5), -1.0);
+                let r = Ray::new(origin, direction, 0.0);
+                let ray_t = Interval::new(0.001, f64::INFINITY);
+                let expected = list.hit(&r, ray_t).map(|rec| rec.t);
+                assert_eq!(bvh.hit(&r, ray_t).map(|rec| rec.t), expected, "{method:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_every_primitive() {
+        let bvh = BvhNode::new(spheres(20), SplitMethod::Sah);
+        let bbox = bvh.bounding_box();
+        for object in spheres(20).objects {
+            let b = object.bounding_box();
+            assert!(bbox.x.min <= b.x.min && b.x.max <= bbox.x.max);
+            assert!(bbox.y.min <= b.y.min && b.y.max <= bbox.y.max);
+            assert!(bbox.z.min <= b.z.min && b.z.max <= bbox.z.max);
+        }
+    }
+
+    #[test]
+    fn empty_lists_never_hit() {
+        let bvh = BvhNode::new(HittableList::new(), SplitMethod::Midpoint);
+        let r = Ray::new(Point3::zero(), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(bvh.hit(&r, Interval::new(0.001, f64::INFINITY)).is_none());
+    }
+}
\ No newline at end of file
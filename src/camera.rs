@@ -1,24 +1,203 @@
 use crate::prelude::*;
 
-use std::{cmp::max, fs::File, io::{BufWriter, Write}};
+use std::cmp::max;
 
 use indicatif::ProgressBar;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use vec3::{cross, random_in_unit_disk};
+use serde::{Deserialize, Serialize};
+use vec3::cross;
+
+use crate::animation::AnimatedTransform;
+use crate::color::luminance;
+use crate::framebuffer::Framebuffer;
+use crate::material::ScatterKind;
+use crate::lights::LightList;
+use crate::pdf::Pdf;
+use crate::sampler::{sample_concentric_disk, SamplerKind};
+
+/// Weighs two sampling strategies that can produce the same light path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MisHeuristic {
+    /// Weights proportional to the densities.
+    Balance,
+    /// Weights proportional to the squared densities, favoring the stronger strategy more.
+    #[default]
+    Power,
+}
+
+impl MisHeuristic {
+    /// The weight of a sample drawn with density `pdf` when `other_pdf` could also have
+    /// produced it.
+    pub fn weight(self, pdf: f64, other_pdf: f64) -> f64 {
+        let (a, b) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf),
+        };
+        if a.is_infinite() { return 1.0; }
+        if a + b > 0.0 { a / (a + b) } else { 0.0 }
+    }
+}
+
+/// How far open the shutter is over the exposure, as a piecewise linear curve through points
+/// `(t, openness)` where `t` runs from 0 at shutter open to 1 at shutter close. Ray times are
+/// drawn in proportion to the openness. The default is a box: fully open throughout.
+#[derive(Debug, Clone)]
+pub struct ShutterCurve {
+    points: Vec<(f64, f64)>,
+    /// Area under the curve up to the end of each segment.
+    cdf: Vec<f64>,
+}
+
+impl Default for ShutterCurve {
+    fn default() -> Self {
+        Self::new(vec![(0.0, 1.0), (1.0, 1.0)]).unwrap()
+    }
+}
+
+impl ShutterCurve {
+    /// Checks that the points start at 0, end at 1, increase in `t` and enclose some area.
+    pub fn new(points: Vec<(f64, f64)>) -> Result<Self, String> {
+        if points.len() < 2 {
+            return Err("a shutter curve needs at least two points".to_string());
+        }
+        if points[0].0 != 0.0 || points[points.len() - 1].0 != 1.0 {
+            return Err("a shutter curve must run from t = 0 to t = 1".to_string());
+        }
+        if points.windows(2).any(|pair| pair[1].0 <= pair[0].0) {
+            return Err("shutter curve times must increase".to_string());
+        }
+        if points.iter().any(|&(_, openness)| !(openness >= 0.0 && openness.is_finite())) {
+            return Err("shutter curve openness must not be negative".to_string());
+        }
+
+        let mut area = 0.0;
+        let cdf: Vec<f64> = points.windows(2)
+            .map(|pair| {
+                area += 0.5 * (pair[0].1 + pair[1].1) * (pair[1].0 - pair[0].0);
+                area
+            })
+            .collect();
+        if area <= 0.0 {
+            return Err("a shutter curve must be open at some point".to_string());
+        }
+        Ok(Self { points, cdf })
+    }
+
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    pub fn is_box(&self) -> bool {
+        self.points.iter().all(|&(_, openness)| openness == self.points[0].1)
+    }
+
+    /// Maps `u` in [0, 1) to a fraction of the exposure, distributed like the curve.
+    pub fn sample(&self, u: f64) -> f64 {
+        let target = u * self.cdf[self.cdf.len() - 1];
+        let segment = self.cdf.partition_point(|&c| c < target).min(self.cdf.len() - 1);
+        let before = if segment == 0 { 0.0 } else { self.cdf[segment - 1] };
+        let ((t0, a), (t1, b)) = (self.points[segment], self.points[segment + 1]);
+
+        // Invert the area under the line from a to b: a x + (b - a) x² / 2w = target.
+        let (w, area) = (t1 - t0, target - before);
+        let slope = (b - a) / w;
+        let x = if slope.abs() < 1e-12 {
+            if a > 0.0 { area / a } else { 0.0 }
+        } else {
+            (-a + (a * a + 2.0 * slope * area).max(0.0).sqrt()) / slope
+        };
+        t0 + x.clamp(0.0, w)
+    }
+}
+
+/// A rendered image along with the number of samples each pixel took.
+pub struct Render {
+    pub image: Framebuffer,
+    pub samples: Vec<u32>,
+    pub max_samples: u32,
+}
+
+impl Render {
+    /// A grayscale image of the samples spent per pixel, white being `max_samples`.
+    pub fn samples_image(&self) -> Framebuffer {
+        let pixels = self.samples.iter()
+            .map(|&count| {
+                let level = count as f64 / self.max_samples as f64;
+                Color::new(level, level, level)
+            })
+            .collect();
+        Framebuffer::from_pixels(self.image.width(), self.image.height(), pixels)
+    }
+}
+
+/// Running mean and variance of the samples of one pixel, using Welford's algorithm on their
+/// luminance.
+#[derive(Default)]
+struct PixelEstimate {
+    count: u32,
+    sum: Color,
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelEstimate {
+    fn add(&mut self, sample: Color) {
+        self.count += 1;
+        self.sum += sample;
+
+        let value = luminance(&sample);
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Whether the 95% confidence interval is within `threshold` of the mean. Very dark pixels
+    /// are held to the precision of a pixel at 1% brightness rather than an ever smaller one.
+    fn converged(&self, threshold: f64) -> bool {
+        if self.count < 2 { return false; }
+
+        let n = self.count as f64;
+        let variance = self.m2 / (n - 1.0);
+        let error = 1.96 * (variance / n).sqrt();
+        error <= threshold * self.mean.max(0.01)
+    }
+}
 
 pub struct Camera {
     pub aspect_ratio: f64,
     pub image_width: u32,
+    /// Samples taken for each pixel, or the most taken when sampling adaptively.
     pub samples_per_pixel: u32,
+    /// Enables adaptive sampling: a pixel stops taking samples once the 95% confidence interval
+    /// of its brightness is within this fraction of the brightness itself.
+    pub adaptive_threshold: Option<f64>,
+    /// Samples every pixel takes before adaptive sampling may stop it.
+    pub min_samples_per_pixel: u32,
     pub max_depth: i32,
+    /// Bounces after which Russian roulette may end a path early.
+    pub roulette_depth: u32,
     pub vfov: f64,
     pub look_from: Point3,
     pub look_at: Point3,
     pub vup: Vec3,
     pub defocus_angle: f64,
     pub focus_dist: f64,
+    pub background: Background,
+    /// Seeds the random samples; renders with the same seed are identical.
+    pub seed: u64,
+    /// How light samples and scattered rays share the direct lighting.
+    pub mis: MisHeuristic,
+    /// Generates the random numbers of every sample.
+    pub sampler: SamplerKind,
+    /// The time the shutter opens. Ray times fall between this and `shutter_close`, both
+    /// within [0, 1], the span over which moving spheres travel.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    pub shutter_curve: ShutterCurve,
+    /// Moves the whole camera over time, for camera motion blur.
+    pub motion: Option<AnimatedTransform>,
     image_height: u32,
-    pixel_samples_scale: f64,
     center: Point3,
     pixel00_loc: Point3,
     pixel_delta_u: Vec3,
@@ -28,6 +207,7 @@ pub struct Camera {
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         aspect_ratio: f64, 
         image_width: u32, 
@@ -41,21 +221,56 @@ impl Camera {
         focus_dist: f64,
     
     ) -> Self {
-        let image_height = max(1, (image_width as f64 / aspect_ratio) as u32);
+        let mut camera = Self {
+            aspect_ratio,
+            image_width,
+            samples_per_pixel,
+            adaptive_threshold: None,
+            min_samples_per_pixel: 16,
+            max_depth,
+            roulette_depth: 3,
+            vfov,
+            look_from,
+            look_at,
+            vup,
+            defocus_angle,
+            focus_dist,
+            background: Background::default(),
+            seed: 0,
+            mis: MisHeuristic::default(),
+            sampler: SamplerKind::default(),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            shutter_curve: ShutterCurve::default(),
+            motion: None,
+            image_height: 0,
+            center: Point3::zero(),
+            pixel00_loc: Point3::zero(),
+            pixel_delta_u: Vec3::zero(),
+            pixel_delta_v: Vec3::zero(),
+            defocus_disk_u: Vec3::zero(),
+            defocus_disk_v: Vec3::zero(),
+        };
+        camera.initialize();
+        camera
+    }
 
-        let pixel_samples_scale = 1.0 / samples_per_pixel as f64;
+    /// Recomputes the viewport from the public fields. Call this after changing any of them.
+    pub fn initialize(&mut self) {
+        self.image_height = max(1, (self.image_width as f64 / self.aspect_ratio) as u32);
 
-        let center = look_from;
+        self.center = self.look_from;
         
         // Determine viewport dimensions
-        let theta = vfov.to_radians();
+        let theta = self.vfov.to_radians();
         let h = f64::tan(theta / 2.0);
-        let viewport_height = 2.0 * h * focus_dist;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
+        let viewport_height = 2.0 * h * self.focus_dist;
+        let viewport_width =
+            viewport_height * (self.image_width as f64 / self.image_height as f64);
 
         // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
-        let w = (look_from - look_at).unit_vector();
-        let u = cross(vup, w).unit_vector();
+        let w = (self.look_from - self.look_at).unit_vector();
+        let u = cross(self.vup, w).unit_vector();
         let v = cross(w, u);
 
         // Calculate the vectors across the horizontal and down the vertical viewport edges.
@@ -63,126 +278,300 @@ impl Camera {
         let viewport_v = viewport_height * -v;
 
         // Calculate the horizontal and vertical delta vectors from pixel to pixel.
-        let pixel_delta_u = viewport_u / image_width as f64;
-        let pixel_delta_v = viewport_v / image_height as f64;
+        self.pixel_delta_u = viewport_u / self.image_width as f64;
+        self.pixel_delta_v = viewport_v / self.image_height as f64;
 
         // Calculate the location of the upper left pixel.
         let viewport_upper_left =
-            center 
-                - focus_dist * w 
+            self.center 
+                - self.focus_dist * w 
                 - viewport_u / 2.0 
                 - viewport_v / 2.0;
-        let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
+        self.pixel00_loc = viewport_upper_left + 0.5 * (self.pixel_delta_u + self.pixel_delta_v);
 
         // Calculate the camera defocus disk basis vectors.
         let defocus_radius = 
-            focus_dist * f64::tan((defocus_angle / 2.0).to_radians());
-        let defocus_disk_u = u * defocus_radius;
-        let defocus_disk_v = v * defocus_radius;
-
-        Self {
-            aspect_ratio,
-            image_width,
-            samples_per_pixel,
-            max_depth,
-            vfov,
-            look_from,
-            look_at,
-            vup,
-            defocus_angle,
-            focus_dist,
-            image_height,
-            pixel_samples_scale,
-            center,
-            pixel00_loc,
-            pixel_delta_u,
-            pixel_delta_v,
-            defocus_disk_u,
-            defocus_disk_v,
-        }
+            self.focus_dist * f64::tan((self.defocus_angle / 2.0).to_radians());
+        self.defocus_disk_u = u * defocus_radius;
+        self.defocus_disk_v = v * defocus_radius;
     }
 
-    pub fn get_ray(&self, i: u32, j: u32) -> Ray {
+    pub fn get_ray(&self, i: u32, j: u32, sampler: &mut dyn Sampler) -> Ray {
         // Construct a camera ray originating from the defocus disk and directed at a randomly
-        // sampled point around the pixel location i, j.
-        let offset = Self::sample_square();
+        // sampled point around the pixel location i, j. The lens and time dimensions are drawn
+        // even when unused, so later dimensions line up the same way in every scene.
+        let offset = Self::sample_square(sampler);
         let pixel_sample = self.pixel00_loc
                           + ((i as f64 + offset.x) * self.pixel_delta_u)
                           + ((j as f64 + offset.y) * self.pixel_delta_v);
 
+        let lens_sample = self.defocus_disk_sample(sampler);
         let origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
-            self.defocus_disk_sample()
+            lens_sample
         };
         let direction = pixel_sample - origin;
 
-        let ray_time = random();
+        let shutter = self.shutter_curve.sample(sampler.get_1d());
+        let ray_time = self.shutter_open + shutter * (self.shutter_close - self.shutter_open);
 
-        Ray { origin, direction, time: ray_time }
+        match &self.motion {
+            Some(motion) => {
+                let transform = motion.at(ray_time);
+                Ray::new(transform.point(origin), transform.vector(direction), ray_time)
+            },
+            None => Ray { origin, direction, time: ray_time },
+        }
     }
 
-    fn defocus_disk_sample(&self) -> Point3 {
+    fn defocus_disk_sample(&self, sampler: &mut dyn Sampler) -> Point3 {
         // Returns a random point in the camera defocus disk.
-        let p = random_in_unit_disk();
+        let p = sample_concentric_disk(sampler.get_2d());
         self.center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v)
     }
 
-    fn sample_square() -> Vec3 {
+    fn sample_square(sampler: &mut dyn Sampler) -> Vec3 {
+        let (x, y) = sampler.get_2d();
         Vec3 {
-            x: random::<f64>() - 0.5,
-            y: random::<f64>() - 0.5,
+            x: x - 0.5,
+            y: y - 0.5,
             z: 0.0,
         }
     }
 
-    pub fn ray_color(r: &Ray, max_depth: i32, world: &dyn Hittable) -> Color {
-        // If we've exceeded the ray bounce limit, no more light is gathered.
-        if max_depth <= 0 { return Color::zero(); }
+    /// Traces `r` into the world. At every diffuse bounce, direct light is estimated both by
+    /// sampling `lights` and by following the scattered ray, and the two estimates are combined
+    /// with multiple importance sampling. Participating media scatter and glow at the
+    /// collisions they report as hits, and dim shadow rays by their transmittance.
+    ///
+    /// Paths are followed iteratively, carrying the product of the attenuations so far as the
+    /// throughput. After `roulette_depth` bounces, Russian roulette ends paths with a
+    /// probability that grows as their throughput shrinks, and boosts the survivors to keep the
+    /// estimate unbiased. `max_depth` remains a hard limit on the number of bounces.
+    pub fn ray_color(
+        &self,
+        r: &Ray,
+        world: &dyn Hittable,
+        lights: &LightList,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let mut ray = *r;
+        let mut color = Color::zero();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        // The density the previous bounce sampled `ray` with. It is `None` for camera rays and
+        // after specular bounces, where no light sample competes with the ray.
+        let mut bsdf_pdf: Option<f64> = None;
 
-        if let Some(rec) = world.hit(r, Interval::new(0.001, INFINITY)) {
-            if let Some(scatter_rec) = rec.mat.scatter(r, &rec) {
-                return scatter_rec.attenuation 
-                    * Self::ray_color(&scatter_rec.scattered, max_depth - 1, world);
+        for depth in 0..self.max_depth {
+            let Some(rec) = world.hit_sampled(&ray, Interval::new(0.001, f64::INFINITY), sampler)
+            else {
+                color += throughput * self.background.color(&ray);
+                break;
+            };
+
+            let mut color_from_emission = rec.mat.emitted(&ray, &rec);
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                // Glowing media can't be reached by light samples, so there's nothing to share.
+                if color_from_emission.len_squared() > 0.0 && !rec.mat.is_phase_function() {
+                    let light_pdf = lights.pdf_value(ray.origin, ray.direction);
+                    color_from_emission =
+                        color_from_emission * self.mis.weight(bsdf_pdf, light_pdf);
+                }
+            }
+            color += throughput * color_from_emission;
+
+            let Some(scatter_rec) = rec.mat.scatter(&ray, &rec, sampler) else { break; };
+
+            match scatter_rec.kind {
+                ScatterKind::Specular(scattered) => {
+                    throughput = throughput * scatter_rec.attenuation;
+                    ray = scattered;
+                    bsdf_pdf = None;
+                },
+                ScatterKind::Pdf(pdf) => {
+                    let color_from_lights =
+                        self.sample_lights(&ray, &rec, pdf.as_ref(), world, lights, sampler);
+                    color += throughput * scatter_rec.attenuation * color_from_lights;
+
+                    let scattered = Ray::new(rec.p, pdf.generate(sampler), ray.time);
+                    let pdf_value = pdf.value(scattered.direction);
+                    if pdf_value <= 0.0 { break; }
+
+                    let scattering_pdf = rec.mat.scattering_pdf(&ray, &rec, &scattered);
+                    throughput = throughput * scatter_rec.attenuation * scattering_pdf / pdf_value;
+                    ray = scattered;
+                    bsdf_pdf = Some(pdf_value);
+                },
+            }
+
+            if depth as u32 + 1 >= self.roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if survival <= 0.0 || sampler.get_1d() >= survival { break; }
+                throughput = throughput / survival;
             }
-            return Color::zero();
         }
-    
-        let unit_direction = r.direction.unit_vector();
-        let a = 0.5 * (unit_direction.y + 1.0);
-        (1.0 - a) * Color { x: 1.0, y: 1.0, z: 1.0 } + a * Color { x: 0.5, y: 0.7, z: 1.0 }
+
+        color
     }
 
-    pub fn render(
-        &self, 
-        world: &dyn Hittable, 
-        writer: &mut BufWriter<File>,
-    ) -> std::io::Result<()> 
-    {
+    /// Next-event estimation: the light arriving directly from one sampled point on `lights`,
+    /// weighted against the chance of `bsdf_pdf` finding the same light. The result still has
+    /// to be multiplied by the attenuation.
+    fn sample_lights(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        bsdf_pdf: &dyn Pdf,
+        world: &dyn Hittable,
+        lights: &LightList,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let Some(light) = lights.sample(sampler) else { return Color::zero(); };
+
+        let shadow_ray = Ray::new(rec.p, light.random(rec.p, sampler), r.time);
+        let light_pdf = lights.pdf_value(shadow_ray.origin, shadow_ray.direction);
+        if light_pdf <= 0.0 { return Color::zero(); }
+
+        let scattering_pdf = rec.mat.scattering_pdf(r, rec, &shadow_ray);
+        if scattering_pdf <= 0.0 { return Color::zero(); }
+
+        // Whatever surface the shadow ray hits first is what is seen, whether the light or a
+        // blocker, dimmed by any media on the way.
+        let Some(light_rec) =
+            world.hit_surface(&shadow_ray, Interval::new(0.001, f64::INFINITY))
+        else {
+            return Color::zero();
+        };
+        let emitted = light_rec.mat.emitted(&shadow_ray, &light_rec);
+        if emitted.len_squared() == 0.0 { return Color::zero(); }
+        let transmittance =
+            world.transmittance_sampled(&shadow_ray, Interval::new(0.001, light_rec.t), sampler);
+
+        let weight = self.mis.weight(light_pdf, bsdf_pdf.value(shadow_ray.direction));
+        emitted * (transmittance * weight * scattering_pdf / light_pdf)
+    }
+
+    /// Renders the world into an image of linear (not gamma corrected) radiance.
+    pub fn render(&self, world: &dyn Hittable, lights: &LightList) -> Render {
         let bar = ProgressBar::new(self.image_height as u64);
-        writeln!(*writer, "P3\n{} {}\n255", self.image_width, self.image_height)?;
 
-        let image: Vec<Color> = (0..self.image_height).into_par_iter()
+        let pixels: Vec<(Color, u32)> = (0..self.image_height).into_par_iter()
             .flat_map(|y| {
                 bar.inc(1);
                 (0..self.image_width)
-                    .map(|x| {
-                        (0..self.samples_per_pixel).map(|_| {
-                            let r = self.get_ray(x, y);
-                            Self::ray_color(&r, self.max_depth, world)
-                        }).sum()
-                    })
-                    .collect::<Vec<Color>>()
+                    .map(|x| self.render_pixel(x, y, world, lights))
+                    .collect::<Vec<_>>()
             }).collect();
 
+        bar.finish();
+        let (image, samples) = pixels.into_iter().unzip();
+        Render {
+            image: Framebuffer::from_pixels(self.image_width, self.image_height, image),
+            samples,
+            max_samples: self.samples_per_pixel,
+        }
+    }
+
+    /// Averages the samples of pixel `x`, `y` and returns the result with the number of
+    /// samples taken.
+    fn render_pixel(&self, x: u32, y: u32, world: &dyn Hittable, lights: &LightList) -> (Color, u32) {
+        let sampler = &mut *self.sampler.create(self.seed, self.samples_per_pixel);
+        let min_samples = self.min_samples_per_pixel.min(self.samples_per_pixel);
+        let mut estimate = PixelEstimate::default();
+
+        for index in 0..self.samples_per_pixel {
+            sampler.start_sample(x, y, index);
+            let r = self.get_ray(x, y, sampler);
+            estimate.add(self.ray_color(&r, world, lights, sampler));
 
-        for pixel in image {
-            write_color(writer, &(self.pixel_samples_scale * pixel))?;
+            if let Some(threshold) = self.adaptive_threshold {
+                if estimate.count >= min_samples && estimate.converged(threshold) { break; }
+            }
         }
-    
-        writer.flush()?;
-        bar.finish();
-        Ok(())
+
+        (estimate.sum / estimate.count as f64, estimate.count)
     }
     
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(image_width: u32, aspect_ratio: f64) -> Camera {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let look_from = Point3::new(0.0, 0.0, 1.0);
+        Camera::new(aspect_ratio, image_width, 1, 5, 90.0, look_from, Point3::zero(), up, 0.0, 1.0)
+    }
+
+    #[test]
+    fn image_height_truncates_and_is_at_least_one() {
+        assert_eq!(camera(400, 16.0 / 9.0).image_height, 225);
+        assert_eq!(camera(100, 1.5).image_height, 66);
+        assert_eq!(camera(10, 100.0).image_height, 1);
+    }
+
+    #[test]
+    fn constant_pixels_converge_and_noisy_ones_do_not() {
+        let mut steady = PixelEstimate::default();
+        let mut noisy = PixelEstimate::default();
+        for i in 0..16 {
+            steady.add(Color::new(0.5, 0.5, 0.5));
+            noisy.add(if i % 2 == 0 { Color::zero() } else { Color::new(1.0, 1.0, 1.0) });
+        }
+        assert!(steady.converged(0.01));
+        assert!(!noisy.converged(0.01));
+        assert!(noisy.converged(1.0));
+
+        let mut single = PixelEstimate::default();
+        single.add(Color::new(0.5, 0.5, 0.5));
+        assert!(!single.converged(1.0));
+    }
+
+    #[test]
+    fn shutter_curves_are_validated() {
+        let error = |points: Vec<(f64, f64)>| ShutterCurve::new(points).unwrap_err();
+        assert_eq!(error(vec![(0.0, 1.0)]), "a shutter curve needs at least two points");
+        assert_eq!(error(vec![(0.0, 1.0), (0.5, 1.0)]), "a shutter curve must run from t = 0 to t = 1");
+        assert_eq!(
+            error(vec![(0.0, 1.0), (0.6, 1.0), (0.4, 1.0), (1.0, 1.0)]),
+            "shutter curve times must increase",
+        );
+        assert_eq!(error(vec![(0.0, 1.0), (1.0, -0.5)]), "shutter curve openness must not be negative");
+        assert_eq!(error(vec![(0.0, 0.0), (1.0, 0.0)]), "a shutter curve must be open at some point");
+    }
+
+    #[test]
+    fn shutter_times_follow_the_openness() {
+        let ramp = ShutterCurve::new(vec![(0.0, 0.0), (1.0, 1.0)]).unwrap();
+        for u in [0.0, 0.1, 0.25, 0.5, 0.9] {
+            assert!((ramp.sample(u) - f64::sqrt(u)).abs() < 1e-12);
+        }
+        let open = ShutterCurve::default();
+        assert!(open.is_box());
+        assert!((open.sample(0.3) - 0.3).abs() < 1e-12);
+    }
+
+    /// Renders a small version of a preset on a thread pool of the given size.
+    fn render_preset(name: &str, threads: usize) -> Vec<[f64; 3]> {
+        let preset = crate::presets::find(name).unwrap();
+        let scene = (preset.build)(&mut crate::rng::seeded(3));
+        let mut cam = scene.camera;
+        cam.image_width = 24;
+        cam.samples_per_pixel = 4;
+        cam.seed = 3;
+        cam.initialize();
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        let render = pool.install(|| cam.render(&scene.world, &scene.lights));
+        render.image.pixels().iter().map(|p| [p.x, p.y, p.z]).collect()
+    }
+
+    #[test]
+    fn renders_are_identical_across_thread_counts() {
+        for name in ["bouncing-spheres", "cornell-smoke"] {
+            assert_eq!(render_preset(name, 1), render_preset(name, 8), "{name}");
+        }
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use std::{fs::File, io::{ BufWriter, Write }};
+use std::io::Write;
 
 use crate::prelude::*;
 
@@ -10,20 +10,28 @@ fn linear_to_gamma(linear_component: f64) -> f64 {
     }
 }
 
-pub fn write_color(writer: &mut BufWriter<File>, pixel_color: &Color) -> std::io::Result<()> {
-    let r = pixel_color.x;
-    let g = pixel_color.y;
-    let b = pixel_color.z;
+fn gamma_components(pixel_color: &Color) -> [f64; 3] {
+    [pixel_color.x, pixel_color.y, pixel_color.z].map(linear_to_gamma)
+}
+
+/// Perceived brightness of a linear color, using the Rec. 709 weights.
+pub fn luminance(c: &Color) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
 
-    let r = linear_to_gamma(r);
-    let g = linear_to_gamma(g);
-    let b = linear_to_gamma(b);
-    
+pub fn to_rgb8(pixel_color: &Color) -> [u8; 3] {
     // Translate the [0,1] component values to the byte range [0,255].
     let intensity = Interval::new(0.0, 0.999);
-    let rbyte = (256.0 * intensity.clamp(r)) as u32;
-    let gbyte = (256.0 * intensity.clamp(g)) as u32;
-    let bbyte = (256.0 * intensity.clamp(b)) as u32;
+    gamma_components(pixel_color).map(|c| (256.0 * intensity.clamp(c)) as u8)
+}
+
+pub fn to_rgb16(pixel_color: &Color) -> [u16; 3] {
+    let intensity = Interval::new(0.0, 1.0);
+    gamma_components(pixel_color).map(|c| (65535.0 * intensity.clamp(c)).round() as u16)
+}
+
+pub fn write_color(writer: &mut impl Write, pixel_color: &Color) -> std::io::Result<()> {
+    let [rbyte, gbyte, bbyte] = to_rgb8(pixel_color);
 
     // Write out the pixel color components.
     writeln!(writer, "{rbyte} {gbyte} {bbyte}")?;
@@ -0,0 +1,146 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use vec3::cross;
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::sampler::sample_concentric_disk;
+use crate::scene::ObjectDescription;
+
+/// Flat circular disk facing along `normal`.
+pub struct Disk {
+    center: Point3,
+    radius: f64,
+    normal: Vec3,
+    u_axis: Vec3,
+    v_axis: Vec3,
+    d: f64,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        let normal = normal.unit_vector();
+        let d = dot(normal, center);
+
+        // Any two unit vectors orthogonal to the normal will do for the texture frame.
+        let a = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let u_axis = cross(normal, a).unit_vector();
+        let v_axis = cross(normal, u_axis);
+
+        // The extent of a disk along each axis is radius * sin(angle between axis and normal).
+        let extent = Vec3::new(
+            radius * (1.0 - normal.x * normal.x).max(0.0).sqrt(),
+            radius * (1.0 - normal.y * normal.y).max(0.0).sqrt(),
+            radius * (1.0 - normal.z * normal.z).max(0.0).sqrt(),
+        );
+        let bbox = Aabb::from_points(center - extent, center + extent);
+
+        Self { center, radius, normal, u_axis, v_axis, d, mat, bbox }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let denom = dot(self.normal, r.direction);
+
+        // No hit if the ray is parallel to the plane.
+        if denom.abs() < 1e-8 { return None; }
+
+        let t = (self.d - dot(self.normal, r.origin)) / denom;
+        if !ray_t.surrounds(t) { return None; }
+
+        let p = r.at(t);
+        let offset = p - self.center;
+        if offset.len_squared() > self.radius * self.radius { return None; }
+
+        // Polar texture coordinates: u runs around the rim, v out from the center.
+        let phi = f64::atan2(dot(offset, self.v_axis), dot(offset, self.u_axis)) + PI;
+
+        let mut rec = HitRecord {
+            p,
+            normal: Default::default(),
+            mat: self.mat.clone(),
+            t,
+            u: phi / (2.0 * PI),
+            v: offset.len() / self.radius,
+            front_face: Default::default(),
+        };
+        rec.set_face_normal(r, self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let Some(rec) = self.hit(&Ray::new(origin, direction, 0.0), Interval::new(0.001, f64::INFINITY))
+        else {
+            return 0.0;
+        };
+
+        let distance_squared = rec.t * rec.t * direction.len_squared();
+        let cosine = (dot(direction, rec.normal) / direction.len()).abs();
+        distance_squared / (cosine * self.area())
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let d = self.radius * sample_concentric_disk(sampler.get_2d());
+        let p = self.center + d.x * self.u_axis + d.y * self.v_axis;
+        p - origin
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let material = scene.material(&self.mat)?;
+        scene.push_object(ObjectDescription::Disk {
+            center: self.center.into(),
+            normal: self.normal.into(),
+            radius: self.radius,
+            material,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn disk() -> Disk {
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Disk::new(Point3::zero(), Vec3::new(0.0, 2.0, 0.0), 1.0, mat)
+    }
+
+    fn down_at(x: f64, z: f64) -> Ray {
+        Ray::new(Point3::new(x, 3.0, z), Vec3::new(0.0, -1.0, 0.0), 0.0)
+    }
+
+    #[test]
+    fn hits_inside_the_radius_only() {
+        let rec = disk().hit(&down_at(0.6, 0.0), Interval::new(0.001, 10.0)).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-12);
+        assert!((rec.v - 0.6).abs() < 1e-12);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).len() < 1e-12);
+        assert!(disk().hit(&down_at(0.8, 0.8), Interval::new(0.001, 10.0)).is_none());
+    }
+
+    #[test]
+    fn hits_exactly_at_the_interval_bounds_are_rejected() {
+        assert!(disk().hit(&down_at(0.0, 0.0), Interval::new(0.0, 3.0)).is_none());
+        assert!(disk().hit(&down_at(0.0, 0.0), Interval::new(3.0, 4.0)).is_none());
+    }
+
+    #[test]
+    fn bounding_box_is_flat_but_padded() {
+        let bbox = disk().bounding_box();
+        assert!((bbox.x.min + 1.0).abs() < 1e-12 && (bbox.x.max - 1.0).abs() < 1e-12);
+        assert!(bbox.y.len() > 0.0 && bbox.y.len() < 0.001);
+    }
+}
\ No newline at end of file
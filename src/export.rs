@@ -0,0 +1,317 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt, fs,
+    path::Path,
+    sync::Arc,
+};
+
+use crate::prelude::*;
+use crate::scene::{
+    BackgroundDescription, CameraDescription, MaterialDescription, MotionDescription,
+    ObjectDescription, SceneDescription, TextureDescription, TextureRef,
+};
+use crate::texture::Texture;
+
+/// Raised when part of a scene has no scene file representation, such as a custom background
+/// closure or a user-defined material.
+#[derive(Debug)]
+pub struct ExportError(pub String);
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for ExportError {}
+
+impl ExportError {
+    pub fn unsupported(what: &str) -> Self {
+        ExportError(format!("{what} cannot be written to a scene file"))
+    }
+}
+
+/// Collects the description of a world as its objects report themselves. Materials, textures
+/// and instanced prototypes are interned by `Arc` identity, so one shared material is written
+/// once and referenced by name from every object using it.
+#[derive(Default)]
+pub struct SceneExporter {
+    textures: BTreeMap<String, TextureDescription>,
+    texture_names: HashMap<usize, String>,
+    materials: BTreeMap<String, MaterialDescription>,
+    material_names: HashMap<usize, String>,
+    prototypes: BTreeMap<String, Vec<ObjectDescription>>,
+    prototype_names: HashMap<usize, String>,
+    objects: Vec<ObjectDescription>,
+}
+
+impl SceneExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_object(&mut self, object: ObjectDescription) {
+        self.objects.push(object);
+    }
+
+    /// Runs `describe` and returns the objects it pushed instead of keeping them, so that a
+    /// wrapper can write them out nested inside its own description.
+    pub fn capture(
+        &mut self,
+        describe: impl FnOnce(&mut Self) -> Result<(), ExportError>,
+    ) -> Result<Vec<ObjectDescription>, ExportError> {
+        let outer = std::mem::take(&mut self.objects);
+        let result = describe(self);
+        let inner = std::mem::replace(&mut self.objects, outer);
+        result.map(|()| inner)
+    }
+
+    /// Like `capture`, but returns a single object, grouping what `describe` pushed when there
+    /// is more than one. Wrappers use this so the list they wrap is read back as one object.
+    pub fn capture_one(
+        &mut self,
+        describe: impl FnOnce(&mut Self) -> Result<(), ExportError>,
+    ) -> Result<ObjectDescription, ExportError> {
+        let mut objects = self.capture(describe)?;
+        match objects.len() {
+            1 => Ok(objects.pop().unwrap()),
+            _ => Ok(ObjectDescription::Group { objects }),
+        }
+    }
+
+    /// Returns the name under which `mat` is written, describing it on first use.
+    pub fn material(&mut self, mat: &Arc<dyn Material>) -> Result<String, ExportError> {
+        let id = Arc::as_ptr(mat) as *const () as usize;
+        if let Some(name) = self.material_names.get(&id) {
+            return Ok(name.clone());
+        }
+
+        let description = mat.describe(self)?;
+        let name = format!("material_{}", self.materials.len());
+        self.materials.insert(name.clone(), description);
+        self.material_names.insert(id, name.clone());
+        Ok(name)
+    }
+
+    /// Returns the name under which the instanced object `prototype` is written, describing it
+    /// on first use.
+    pub fn prototype(&mut self, prototype: &Arc<dyn Hittable>) -> Result<String, ExportError> {
+        let id = Arc::as_ptr(prototype) as *const () as usize;
+        if let Some(name) = self.prototype_names.get(&id) {
+            return Ok(name.clone());
+        }
+
+        let objects = self.capture(|scene| prototype.describe(scene))?;
+        let name = format!("prototype_{}", self.prototypes.len());
+        self.prototypes.insert(name.clone(), objects);
+        self.prototype_names.insert(id, name.clone());
+        Ok(name)
+    }
+
+    /// Returns a reference to `tex`: solid colors are written inline, anything else is
+    /// described once under `[textures]` and referenced by name.
+    pub fn texture(&mut self, tex: &Arc<dyn Texture>) -> Result<TextureRef, ExportError> {
+        let id = Arc::as_ptr(tex) as *const () as usize;
+        if let Some(name) = self.texture_names.get(&id) {
+            return Ok(TextureRef::Named(name.clone()));
+        }
+
+        let description = tex.describe(self)?;
+        if let TextureDescription::Solid { color } = description {
+            return Ok(TextureRef::Color(color));
+        }
+        let name = format!("texture_{}", self.textures.len());
+        self.textures.insert(name.clone(), description);
+        self.texture_names.insert(id, name.clone());
+        Ok(TextureRef::Named(name))
+    }
+
+    pub fn finish(self, camera: &Camera) -> Result<SceneDescription, ExportError> {
+        Ok(SceneDescription {
+            camera: CameraDescription::from(camera),
+            background: BackgroundDescription::try_from(&camera.background)?,
+            textures: self.textures,
+            materials: self.materials,
+            prototypes: self.prototypes,
+            objects: self.objects,
+        })
+    }
+}
+
+impl From<&Camera> for CameraDescription {
+    fn from(camera: &Camera) -> Self {
+        Self {
+            aspect_ratio: camera.aspect_ratio,
+            image_width: camera.image_width,
+            samples_per_pixel: camera.samples_per_pixel,
+            adaptive_threshold: camera.adaptive_threshold,
+            min_samples_per_pixel: camera.min_samples_per_pixel,
+            max_depth: camera.max_depth,
+            roulette_depth: camera.roulette_depth,
+            vfov: camera.vfov,
+            look_from: camera.look_from.into(),
+            look_at: camera.look_at.into(),
+            vup: camera.vup.into(),
+            defocus_angle: camera.defocus_angle,
+            focus_dist: camera.focus_dist,
+            shutter_open: camera.shutter_open,
+            shutter_close: camera.shutter_close,
+            shutter_curve: (!camera.shutter_curve.is_box()).then(|| {
+                camera.shutter_curve.points().iter().map(|&(t, openness)| [t, openness]).collect()
+            }),
+            motion: camera.motion.as_ref().map(MotionDescription::from),
+            seed: camera.seed,
+            sampler: camera.sampler,
+            mis: camera.mis,
+        }
+    }
+}
+
+impl TryFrom<&Background> for BackgroundDescription {
+    type Error = ExportError;
+
+    fn try_from(background: &Background) -> Result<Self, Self::Error> {
+        match background {
+            Background::Solid(color) => Ok(BackgroundDescription::Solid { color: (*color).into() }),
+            Background::Gradient { bottom, top } => Ok(BackgroundDescription::Gradient {
+                bottom: (*bottom).into(),
+                top: (*top).into(),
+            }),
+            Background::Custom(_) => Err(ExportError::unsupported("a custom background")),
+        }
+    }
+}
+
+/// Describes the camera and world as a scene file structure.
+pub fn describe_scene(
+    camera: &Camera,
+    world: &dyn Hittable,
+) -> Result<SceneDescription, ExportError> {
+    let mut exporter = SceneExporter::new();
+    world.describe(&mut exporter)?;
+    exporter.finish(camera)
+}
+
+/// Writes the camera and world to a TOML scene file that `load_scene` reads back.
+pub fn save_scene(
+    path: impl AsRef<Path>,
+    camera: &Camera,
+    world: &dyn Hittable,
+) -> Result<(), ExportError> {
+    let path = path.as_ref();
+    let description = describe_scene(camera, world)?;
+    let text = toml::to_string(&description)
+        .map_err(|e| ExportError(format!("could not serialize scene: {e}")))?;
+    fs::write(path, text).map_err(|e| ExportError(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::camera::MisHeuristic;
+    use crate::material::{Lambertian, ScatterRecord};
+    use crate::sampler::SamplerKind;
+    use crate::sphere::Sphere;
+
+    /// Implements only the required methods of each trait.
+    struct Plain;
+
+    impl Texture for Plain {
+        fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+            Color::zero()
+        }
+    }
+
+    impl Material for Plain {
+        fn scatter(&self, _r: &Ray, _rec: &HitRecord, _s: &mut dyn Sampler) -> Option<ScatterRecord> {
+            None
+        }
+    }
+
+    impl Hittable for Plain {
+        fn hit(&self, _r: &Ray, _ray_t: Interval) -> Option<HitRecord> {
+            None
+        }
+
+        fn bounding_box(&self) -> Aabb {
+            Aabb::empty()
+        }
+    }
+
+    fn camera() -> Camera {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        Camera::new(1.0, 10, 1, 5, 90.0, Point3::new(0.0, 0.0, 5.0), Point3::zero(), up, 0.0, 5.0)
+    }
+
+    fn export(object: impl Hittable + 'static) -> Result<SceneDescription, ExportError> {
+        let mut world = HittableList::new();
+        world.objects.push(Box::new(object));
+        describe_scene(&camera(), &world)
+    }
+
+    fn sphere(mat: Arc<dyn Material>) -> Sphere {
+        Sphere::stationary(Point3::zero(), 1.0, mat)
+    }
+
+    #[test]
+    fn implementors_without_describe_are_reported_as_unsupported() {
+        let error = export(Plain).unwrap_err();
+        assert_eq!(error.to_string(), "this kind of object cannot be written to a scene file");
+
+        let error = export(sphere(Arc::new(Plain))).unwrap_err();
+        assert_eq!(error.to_string(), "this kind of material cannot be written to a scene file");
+
+        let error = export(sphere(Arc::new(Lambertian::from_texture(Arc::new(Plain))))).unwrap_err();
+        assert_eq!(error.to_string(), "this kind of texture cannot be written to a scene file");
+    }
+
+    #[test]
+    fn shared_materials_are_written_once() {
+        let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let mut world = HittableList::new();
+        world.objects.push(Box::new(sphere(mat.clone())));
+        world.objects.push(Box::new(sphere(mat)));
+
+        let description = describe_scene(&camera(), &world).unwrap();
+        assert_eq!(description.objects.len(), 2);
+        assert_eq!(description.materials.len(), 1);
+    }
+
+    #[test]
+    fn exported_presets_render_the_same() {
+        let dir = TempDir::new().unwrap();
+        for name in ["cornell-smoke", "final-next-week"] {
+            let preset = crate::presets::find(name).unwrap();
+            let scene = (preset.build)(&mut crate::rng::seeded(3));
+            let mut cam = scene.camera;
+            cam.image_width = 16;
+            cam.samples_per_pixel = 2;
+            cam.seed = 7;
+            cam.sampler = SamplerKind::Halton;
+            cam.mis = MisHeuristic::Balance;
+            cam.initialize();
+
+            let path = dir.path().join(format!("{name}.toml"));
+            save_scene(&path, &cam, &scene.world).unwrap();
+            let loaded = crate::scene::load_scene(&path).unwrap();
+            assert_eq!(loaded.lights.len(), scene.lights.len(), "{name}");
+
+            let pixels = |cam: &Camera, world: &HittableList, lights| {
+                let render = cam.render(world, lights);
+                render.image.pixels().iter().map(|p| [p.x, p.y, p.z]).collect::<Vec<_>>()
+            };
+            let original = pixels(&cam, &scene.world, &scene.lights);
+            // Scene files store matrices but not their inverses, which are recomputed on load
+            // and may differ from the originals in the last bits.
+            let reloaded = pixels(&loaded.camera, &loaded.world, &loaded.lights);
+            let close = |a: f64, b: f64| (a - b).abs() <= 1e-6 * a.abs().max(1.0);
+            assert!(
+                original.iter().zip(&reloaded).all(|(a, b)| (0..3).all(|i| close(a[i], b[i]))),
+                "{name}",
+            );
+        }
+    }
+}
\ No newline at end of file
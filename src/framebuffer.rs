@@ -0,0 +1,76 @@
+use crate::prelude::*;
+
+/// An owned image of linear radiance values, stored row by row from the top left.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    /// Creates an all-black image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::from_pixels(width, height, vec![Color::zero(); (width * height) as usize])
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (width * height) as usize,
+            "pixel count doesn't match a {width}x{height} image",
+        );
+        Self { width, height, pixels }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        let idx = self.index(x, y);
+        self.pixels[idx] = color;
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel ({x}, {y}) out of bounds");
+        (y * self.width + x) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_are_stored_row_by_row_from_the_top_left() {
+        let mut image = Framebuffer::new(3, 2);
+        image.set_pixel(2, 0, Color::new(1.0, 0.0, 0.0));
+        image.set_pixel(0, 1, Color::new(0.0, 1.0, 0.0));
+        assert_eq!(image.pixels()[2].x, 1.0);
+        assert_eq!(image.pixels()[3].y, 1.0);
+        assert_eq!(image.pixel(0, 1).y, 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn out_of_bounds_pixels_panic() {
+        Framebuffer::new(3, 2).pixel(3, 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,251 @@
+use std::{fs, path::Path};
+
+use crate::prelude::*;
+use crate::perlin::Perlin;
+use crate::rng;
+use crate::scene::GridDescription;
+
+/// A dense 3D grid of non-negative values, such as the density of smoke exported from a fluid
+/// simulation. The grid spans the unit cube; values are stored with x varying fastest, then y,
+/// then z, and are interpolated trilinearly between voxel centers.
+pub struct DensityGrid {
+    size: [usize; 3],
+    values: Vec<f32>,
+    max: f64,
+    /// Where the values came from, so exported scenes can recreate them.
+    source: Option<GridDescription>,
+}
+
+impl DensityGrid {
+    /// A grid of `size[0] * size[1] * size[2]` values in x-fastest order.
+    pub fn new(size: [usize; 3], values: Vec<f32>) -> Result<Self, String> {
+        if size.contains(&0) {
+            return Err(format!("size {size:?} must be positive on every axis"));
+        }
+        if values.len() != size.iter().product::<usize>() {
+            return Err(format!("{} values don't fill a grid of size {size:?}", values.len()));
+        }
+        if let Some(bad) = values.iter().find(|v| !(v.is_finite() && **v >= 0.0)) {
+            return Err(format!("value {bad} must be finite and not negative"));
+        }
+
+        let max = values.iter().fold(0.0_f32, |acc, &v| acc.max(v)) as f64;
+        Ok(Self { size, values, max, source: None })
+    }
+
+    /// Reads a NumPy `.npy` file holding a 3D array of 32- or 64-bit floats, indexed `[z][y][x]`
+    /// (or `[x][y][z]` in Fortran order). Any other file is read as raw little-endian 32-bit
+    /// floats in x-fastest order, and its `size` must be given.
+    pub fn load(path: impl AsRef<Path>, size: Option<[usize; 3]>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+
+        let is_npy = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("npy"));
+        let (file_size, values) = if is_npy {
+            read_npy(&bytes)?
+        } else {
+            let Some(size) = size else {
+                return Err("raw grids need a size".to_string());
+            };
+            if bytes.len() % 4 != 0 {
+                return Err(format!("{} bytes is not a whole number of floats", bytes.len()));
+            }
+            let values = bytes.chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            (size, values)
+        };
+        if let Some(size) = size {
+            if size != file_size {
+                return Err(format!("file holds a grid of size {file_size:?}, not {size:?}"));
+            }
+        }
+
+        let mut grid = Self::new(file_size, values)?;
+        // Remember where the grid came from so exported scenes can find it again.
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        grid.source = Some(GridDescription::File { path, size });
+        Ok(grid)
+    }
+
+    /// A billowing cloud: a ball fading out towards the edges of the grid, its surface broken
+    /// up by Perlin turbulence of frequency `scale`. The same seed always gives the same cloud.
+    pub fn noise(size: [usize; 3], scale: f64, seed: u64) -> Self {
+        let noise = Perlin::new(&mut rng::seeded(seed));
+        let mut values = Vec::with_capacity(size.iter().product());
+        for k in 0..size[2] {
+            for j in 0..size[1] {
+                for i in 0..size[0] {
+                    // The voxel center, with the grid spanning [-1, 1] on every axis.
+                    let q = Point3::new(
+                        2.0 * (i as f64 + 0.5) / size[0] as f64 - 1.0,
+                        2.0 * (j as f64 + 0.5) / size[1] as f64 - 1.0,
+                        2.0 * (k as f64 + 0.5) / size[2] as f64 - 1.0,
+                    );
+                    let density = 1.0 - q.len() + 0.5 * noise.turb(&(scale * q), 5);
+                    values.push(density.max(0.0) as f32);
+                }
+            }
+        }
+
+        let mut grid = Self::new(size, values).expect("noise is finite and not negative");
+        grid.source = Some(GridDescription::Noise { size, scale, seed });
+        grid
+    }
+
+    pub fn size(&self) -> [usize; 3] {
+        self.size
+    }
+
+    /// The largest value in the grid, which bounds every interpolated value.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    pub fn source(&self) -> Option<&GridDescription> {
+        self.source.as_ref()
+    }
+
+    /// The interpolated value at `p`, in coordinates where the grid spans the unit cube. Zero
+    /// outside the cube.
+    pub fn value(&self, p: Point3) -> f64 {
+        let unit = Interval::new(0.0, 1.0);
+        if !(unit.contains(p.x) && unit.contains(p.y) && unit.contains(p.z)) { return 0.0; }
+
+        // Voxel centers sit at half-integer positions; past the outermost ones, the value holds.
+        let mut lower = [0; 3];
+        let mut frac = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.size[axis];
+            let x = (p[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            lower[axis] = (x as usize).min(n.saturating_sub(2));
+            frac[axis] = x - lower[axis] as f64;
+        }
+
+        let mut accum = 0.0;
+        for corner in 0..8 {
+            let mut index = 0;
+            let mut weight = 1.0;
+            for axis in (0..3).rev() {
+                let upper = corner >> axis & 1 == 1;
+                let i = (lower[axis] + upper as usize).min(self.size[axis] - 1);
+                index = index * self.size[axis] + i;
+                weight *= if upper { frac[axis] } else { 1.0 - frac[axis] };
+            }
+            if weight > 0.0 {
+                accum += weight * self.values[index] as f64;
+            }
+        }
+        accum
+    }
+}
+
+/// Parses a `.npy` file into the grid size, x first, and its values in x-fastest order.
+fn read_npy(bytes: &[u8]) -> Result<([usize; 3], Vec<f32>), String> {
+    let rest = bytes.strip_prefix(b"\x93NUMPY").ok_or("not a NumPy file")?;
+    let truncated = || "truncated NumPy header".to_string();
+
+    // Version 1 stores the header length in two bytes, later versions in four.
+    let major = *rest.first().ok_or_else(truncated)?;
+    let (header_len, rest) = match major {
+        1 => {
+            let len = rest.get(2..4).ok_or_else(truncated)?;
+            (u16::from_le_bytes([len[0], len[1]]) as usize, &rest[4..])
+        },
+        _ => {
+            let len = rest.get(2..6).ok_or_else(truncated)?;
+            (u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize, &rest[6..])
+        },
+    };
+    let header = rest.get(..header_len).ok_or_else(truncated)?;
+    let header = std::str::from_utf8(header).map_err(|_| "NumPy header is not text".to_string())?;
+    let data = &rest[header_len..];
+
+    let field = |name: &str| {
+        let start = header.find(&format!("'{name}':")).ok_or(format!("NumPy header has no {name}"))?;
+        Ok::<_, String>(header[start + name.len() + 3..].trim_start())
+    };
+
+    let descr = field("descr")?;
+    let descr = descr.split('\'').nth(1).unwrap_or_default();
+    let fortran_order = field("fortran_order")?.starts_with("True");
+    let shape = field("shape")?;
+    let shape: Vec<usize> = shape.trim_start_matches('(')
+        .split(')')
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse().map_err(|_| format!("bad NumPy shape entry \"{dim}\"")))
+        .collect::<Result<_, _>>()?;
+    let [a, b, c] = shape[..] else {
+        return Err(format!("NumPy array has shape {shape:?}, not three dimensions"));
+    };
+    // Either way, x varies fastest in memory.
+    let size = if fortran_order { [a, b, c] } else { [c, b, a] };
+
+    let values: Vec<f32> = match descr {
+        "<f4" => data.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        "<f8" => data.chunks_exact(8)
+            .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32)
+            .collect(),
+        _ => return Err(format!("NumPy arrays of type \"{descr}\" aren't supported, only <f4 and <f8")),
+    };
+    Ok((size, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A version 1 `.npy` file holding `values` as little-endian 32-bit floats.
+    fn npy(shape: &str, fortran_order: bool, values: &[f32]) -> Vec<u8> {
+        let order = if fortran_order { "True" } else { "False" };
+        let mut header = format!("{{'descr': '<f4', 'fortran_order': {order}, 'shape': {shape}, }}");
+        while (header.len() + 11) % 16 != 0 { header.push(' '); }
+        header.push('\n');
+
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend((header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        bytes.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn rejects_grids_that_do_not_add_up() {
+        assert!(DensityGrid::new([2, 0, 1], vec![]).is_err());
+        assert!(DensityGrid::new([2, 2, 1], vec![1.0; 3]).is_err());
+        assert!(DensityGrid::new([1, 1, 1], vec![-1.0]).is_err());
+        assert!(DensityGrid::new([1, 1, 1], vec![f32::NAN]).is_err());
+        assert_eq!(DensityGrid::new([2, 1, 1], vec![0.5, 2.0]).unwrap().max(), 2.0);
+    }
+
+    #[test]
+    fn values_interpolate_between_voxel_centers_and_vanish_outside() {
+        let grid = DensityGrid::new([2, 1, 1], vec![1.0, 3.0]).unwrap();
+        assert_eq!(grid.value(Point3::new(0.25, 0.5, 0.5)), 1.0);
+        assert_eq!(grid.value(Point3::new(0.5, 0.5, 0.5)), 2.0);
+        assert_eq!(grid.value(Point3::new(0.75, 0.5, 0.5)), 3.0);
+        assert_eq!(grid.value(Point3::new(1.0, 0.5, 0.5)), 3.0);
+        assert_eq!(grid.value(Point3::new(1.5, 0.5, 0.5)), 0.0);
+    }
+
+    #[test]
+    fn reads_npy_files_with_x_fastest() {
+        let values = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let (size, read) = read_npy(&npy("(1, 2, 3)", false, &values)).unwrap();
+        assert_eq!(size, [3, 2, 1]);
+        assert_eq!(read, values);
+
+        let (size, _) = read_npy(&npy("(3, 2, 1)", true, &values)).unwrap();
+        assert_eq!(size, [3, 2, 1]);
+
+        let error = read_npy(&npy("(6,)", false, &values)).unwrap_err();
+        assert!(error.contains("not three dimensions"), "{error}");
+        assert!(read_npy(b"not numpy").is_err());
+    }
+}
\ No newline at end of file
@@ -1,9 +1,144 @@
 use std::sync::Arc;
 
 use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
 
-pub trait Hittable: Sync {
+pub trait Hittable: Send + Sync {
     fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord>;
+
+    fn bounding_box(&self) -> Aabb;
+
+    /// Like `hit`, but passes through participating media, which only dim the ray. Shadow rays
+    /// use this together with `transmittance` to see the light behind a cloud.
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.hit(r, ray_t)
+    }
+
+    /// The fraction of light left after passing through any participating media along `r`
+    /// within `ray_t`. Surfaces are ignored. The estimate may be random, but is right on average.
+    fn transmittance(&self, _r: &Ray, _ray_t: Interval) -> f64 {
+        1.0
+    }
+
+    /// Like `hit`, but with any random decisions, such as where a ray scatters inside a
+    /// medium, drawn from `sampler`. Renders go through this so that every random number of a
+    /// path comes from its sampler. Only media and the objects containing others override it.
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        self.hit(r, ray_t)
+    }
+
+    /// Like `transmittance`, but drawing its random decisions from `sampler`.
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, _sampler: &mut dyn Sampler) -> f64 {
+        self.transmittance(r, ray_t)
+    }
+
+    /// Surface area, used to weigh lights by their power. Zero for objects that can't be sampled.
+    fn area(&self) -> f64 {
+        0.0
+    }
+
+    /// The density, with respect to solid angle, of `random` generating `direction` from
+    /// `origin`. Only objects that can be sampled as lights implement this.
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    /// A direction from `origin` towards a random point on the object.
+    fn random(&self, _origin: Point3, _sampler: &mut dyn Sampler) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+
+    /// Adds the scene file description of this object to `scene`. Objects that have none can
+    /// leave this out, and exporting a scene containing them fails.
+    fn describe(&self, _scene: &mut SceneExporter) -> Result<(), ExportError> {
+        Err(ExportError::unsupported("this kind of object"))
+    }
+}
+
+/// Forwards to the boxed object, so wrappers generic over `Hittable` accept boxed objects.
+impl<H: Hittable + ?Sized> Hittable for Box<H> {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        (**self).hit(r, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (**self).bounding_box()
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        (**self).hit_surface(r, ray_t)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        (**self).transmittance(r, ray_t)
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        (**self).hit_sampled(r, ray_t, sampler)
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        (**self).transmittance_sampled(r, ray_t, sampler)
+    }
+
+    fn area(&self) -> f64 {
+        (**self).area()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        (**self).random(origin, sampler)
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        (**self).describe(scene)
+    }
+}
+
+/// Forwards to the shared object, so one geometry can be placed many times without copying it.
+impl<H: Hittable + ?Sized> Hittable for Arc<H> {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        (**self).hit(r, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (**self).bounding_box()
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        (**self).hit_surface(r, ray_t)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        (**self).transmittance(r, ray_t)
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        (**self).hit_sampled(r, ray_t, sampler)
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        (**self).transmittance_sampled(r, ray_t, sampler)
+    }
+
+    fn area(&self) -> f64 {
+        (**self).area()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        (**self).random(origin, sampler)
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        (**self).describe(scene)
+    }
 }
 
 #[derive(Clone)]
@@ -12,6 +147,8 @@ pub struct HitRecord {
     pub normal: Vec3,
     pub mat: Arc<dyn Material>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
 }
 
@@ -1,5 +1,7 @@
 use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
 
+#[derive(Default)]
 pub struct HittableList {
     pub objects: Vec<Box<dyn Hittable>>,
 }
@@ -24,4 +26,70 @@ impl Hittable for HittableList {
         }
         temp_rec
     }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut temp_rec = None;
+        let mut closest_so_far = ray_t.max;
+
+        for object in &self.objects {
+            if let Some(rec) = object.hit_surface(r, Interval::new(ray_t.min, closest_so_far)) {
+                closest_so_far = rec.t;
+                temp_rec = Some(rec);
+            }
+        }
+        temp_rec
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        self.objects.iter().map(|object| object.transmittance(r, ray_t)).product()
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let mut temp_rec = None;
+        let mut closest_so_far = ray_t.max;
+
+        for object in &self.objects {
+            if let Some(rec) =
+                object.hit_sampled(r, Interval::new(ray_t.min, closest_so_far), sampler)
+            {
+                closest_so_far = rec.t;
+                temp_rec = Some(rec);
+            }
+        }
+        temp_rec
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        self.objects.iter().map(|object| object.transmittance_sampled(r, ray_t, sampler)).product()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.objects.iter()
+            .fold(Aabb::empty(), |acc, object| Aabb::from_boxes(&acc, &object.bounding_box()))
+    }
+
+    fn area(&self) -> f64 {
+        self.objects.iter().map(|object| object.area()).sum()
+    }
+
+    /// Picks one of the objects uniformly, so the density is the average of theirs.
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        if self.objects.is_empty() { return 0.0; }
+
+        let weight = 1.0 / self.objects.len() as f64;
+        self.objects.iter()
+            .map(|object| weight * object.pdf_value(origin, direction))
+            .sum()
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        if self.objects.is_empty() { return Vec3::new(1.0, 0.0, 0.0); }
+
+        let index = (sampler.get_1d() * self.objects.len() as f64) as usize;
+        self.objects[index.min(self.objects.len() - 1)].random(origin, sampler)
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        self.objects.iter().try_for_each(|object| object.describe(scene))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::scene::{ObjectDescription, TransformDescription};
+use crate::transform::{Transform, Transformed};
+
+/// One placement of a shared prototype object, such as a mesh or a whole BVH. Instances hold
+/// only a reference to the prototype, a transform and an optional material, so thousands of
+/// them cost little more memory than one.
+pub struct Instance {
+    object: Transformed<Arc<dyn Hittable>>,
+    /// Replaces the prototype's materials on every hit when set.
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    pub fn new(prototype: Arc<dyn Hittable>, transform: Transform) -> Self {
+        Self { object: Transformed::new(prototype, transform), material: None }
+    }
+
+    pub fn with_material(mut self, material: Arc<dyn Material>) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn prototype(&self) -> &Arc<dyn Hittable> {
+        self.object.object()
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut rec = self.object.hit(r, ray_t)?;
+        if let Some(material) = &self.material {
+            rec.mat = material.clone();
+        }
+        Some(rec)
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut rec = self.object.hit_surface(r, ray_t)?;
+        if let Some(material) = &self.material {
+            rec.mat = material.clone();
+        }
+        Some(rec)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        self.object.transmittance(r, ray_t)
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let mut rec = self.object.hit_sampled(r, ray_t, sampler)?;
+        if let Some(material) = &self.material {
+            rec.mat = material.clone();
+        }
+        Some(rec)
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        self.object.transmittance_sampled(r, ray_t, sampler)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box()
+    }
+
+    fn area(&self) -> f64 {
+        self.object.area()
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.object.random(origin, sampler)
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let prototype = scene.prototype(self.prototype())?;
+        let material = self.material.as_ref().map(|mat| scene.material(mat)).transpose()?;
+        scene.push_object(ObjectDescription::Instance {
+            prototype,
+            transform: vec![TransformDescription::Matrix(self.object.transform().matrix().m)],
+            material,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{DiffuseLight, Lambertian};
+    use crate::sphere::Sphere;
+
+    fn material_id(mat: &Arc<dyn Material>) -> *const () {
+        Arc::as_ptr(mat) as *const ()
+    }
+
+    #[test]
+    fn instances_share_the_prototype_and_may_override_its_material() {
+        let own: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let glow: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
+        let prototype: Arc<dyn Hittable> =
+            Arc::new(Sphere::stationary(Point3::zero(), 1.0, own.clone()));
+
+        let shift = |x| Transform::translate(Vec3::new(x, 0.0, 0.0));
+        let left = Instance::new(prototype.clone(), shift(-3.0));
+        let right = Instance::new(prototype, shift(3.0)).with_material(glow.clone());
+        assert!(Arc::ptr_eq(left.prototype(), right.prototype()));
+
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let down_at = |x| Ray::new(Point3::new(x, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = left.hit(&down_at(-3.0), ray_t).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-12);
+        assert_eq!(material_id(&rec.mat), material_id(&own));
+        assert_eq!(material_id(&right.hit(&down_at(3.0), ray_t).unwrap().mat), material_id(&glow));
+        assert!(left.hit(&down_at(3.0), ray_t).is_none());
+    }
+}
\ No newline at end of file
@@ -1,5 +1,4 @@
-use crate::prelude::*;
-
+#[derive(Debug, Clone, Copy)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
@@ -10,18 +9,32 @@ impl Interval {
         Self { min, max }
     }
 
+    /// Creates the tightest interval enclosing both input intervals.
+    pub fn enclosing(a: Interval, b: Interval) -> Self {
+        Self { 
+            min: f64::min(a.min, b.min), 
+            max: f64::max(a.max, b.max),
+        }
+    }
+
     pub fn empty() -> Self {
-        Self { min: INFINITY, max: -INFINITY }
+        Self { min: f64::INFINITY, max: -f64::INFINITY }
     }
 
     pub fn universe() -> Self {
-        Self { min: -INFINITY, max: INFINITY }
+        Self { min: -f64::INFINITY, max: f64::INFINITY }
     }
 
     pub fn len(&self) -> f64 {
         self.max - self.min
     }
 
+    /// Returns the interval grown by `delta` in total, half on each side.
+    pub fn expand(&self, delta: f64) -> Self {
+        let padding = delta / 2.0;
+        Self { min: self.min - padding, max: self.max + padding }
+    }
+
     pub fn contains(&self, x: f64) -> bool {
         self.min <= x && x <= self.max
     }
@@ -0,0 +1,160 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::prelude::*;
+use crate::color::luminance;
+
+/// How `LightList` picks the emitter to sample for direct lighting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LightSelection {
+    /// Every light is equally likely.
+    Uniform,
+    /// Lights are picked in proportion to their emitted power, so a dim fill light doesn't take
+    /// samples away from the main source.
+    #[default]
+    Power,
+}
+
+/// The emissive objects of a scene, sampled directly by next-event estimation. Each light is
+/// also part of the world, where it is found by ordinary scattered rays.
+#[derive(Default)]
+pub struct LightList {
+    /// Shared with the world, so each light is built once.
+    lights: Vec<Arc<dyn Hittable>>,
+    power: Vec<f64>,
+    selection: LightSelection,
+    /// Running sum of the selection probabilities.
+    cdf: Vec<f64>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a light giving off `radiance` evenly from its whole surface.
+    pub fn push(&mut self, light: Arc<dyn Hittable>, radiance: Color) {
+        self.power.push(PI * light.area() * luminance(&radiance));
+        self.lights.push(light);
+        self.update_cdf();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn set_selection(&mut self, selection: LightSelection) {
+        self.selection = selection;
+        self.update_cdf();
+    }
+
+    fn update_cdf(&mut self) {
+        let total_power: f64 = self.power.iter().sum();
+        // Fall back to uniform selection when the powers carry no information.
+        let weights: Vec<f64> = match self.selection {
+            LightSelection::Power if total_power > 0.0 && total_power.is_finite() => {
+                self.power.iter().map(|power| power / total_power).collect()
+            },
+            _ => vec![1.0 / self.lights.len() as f64; self.lights.len()],
+        };
+
+        let mut sum = 0.0;
+        self.cdf = weights.iter().map(|weight| { sum += weight; sum }).collect();
+    }
+
+    /// The probability of `sample` picking light `index`.
+    pub fn probability(&self, index: usize) -> f64 {
+        match index {
+            0 => self.cdf[0],
+            _ => self.cdf[index] - self.cdf[index - 1],
+        }
+    }
+
+    /// Picks a light according to the selection strategy.
+    pub fn sample(&self, sampler: &mut dyn Sampler) -> Option<&dyn Hittable> {
+        if self.lights.is_empty() { return None; }
+
+        let u = sampler.get_1d() * self.cdf[self.cdf.len() - 1];
+        let index = self.cdf.partition_point(|&c| c <= u).min(self.lights.len() - 1);
+        Some(self.lights[index].as_ref())
+    }
+
+    /// The density, with respect to solid angle, of picking a light and then sampling
+    /// `direction` from `origin` on it.
+    pub fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        self.lights.iter().enumerate()
+            .map(|(index, light)| self.probability(index) * light.pdf_value(origin, direction))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DiffuseLight;
+    use crate::quad::Quad;
+    use crate::sampler::SamplerKind;
+
+    /// A square light of side `size` centered above the origin, facing down.
+    fn square(size: f64, height: f64) -> Arc<dyn Hittable> {
+        let mat = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
+        let q = Point3::new(-size / 2.0, height, -size / 2.0);
+        Arc::new(Quad::new(q, Vec3::new(size, 0.0, 0.0), Vec3::new(0.0, 0.0, size), mat))
+    }
+
+    fn lights(selection: LightSelection) -> LightList {
+        let mut lights = LightList::new();
+        lights.set_selection(selection);
+        lights.push(square(1.0, 2.0), Color::new(1.0, 1.0, 1.0));
+        lights.push(square(3.0, 4.0), Color::new(1.0, 1.0, 1.0));
+        lights
+    }
+
+    #[test]
+    fn power_selection_weighs_lights_by_emitted_power() {
+        let lights = lights(LightSelection::Power);
+        assert!((lights.probability(0) - 0.1).abs() < 1e-12);
+        assert!((lights.probability(1) - 0.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn uniform_selection_ignores_power() {
+        let lights = lights(LightSelection::Uniform);
+        assert_eq!(lights.probability(0), 0.5);
+        assert_eq!(lights.probability(1), 0.5);
+    }
+
+    #[test]
+    fn dark_lights_fall_back_to_uniform_selection() {
+        let mut lights = LightList::new();
+        lights.push(square(1.0, 2.0), Color::zero());
+        lights.push(square(2.0, 2.0), Color::zero());
+        assert_eq!(lights.probability(0), 0.5);
+    }
+
+    #[test]
+    fn sampling_follows_the_probabilities() {
+        let lights = lights(LightSelection::Power);
+        let mut sampler = SamplerKind::Independent.create(5, 1);
+        sampler.start_sample(0, 0, 0);
+        let picks = 10_000;
+        let small = (0..picks)
+            .filter(|_| lights.sample(sampler.as_mut()).unwrap().area() == 1.0)
+            .count();
+        assert!((small as f64 / picks as f64 - 0.1).abs() < 0.01, "{small}");
+        assert!(LightList::new().sample(sampler.as_mut()).is_none());
+    }
+
+    #[test]
+    fn pdf_mixes_the_light_densities() {
+        let lights = lights(LightSelection::Power);
+        // Straight up crosses both lights: the small one at distance 2, the large one at 4.
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let expected = 0.1 * 4.0 / 1.0 + 0.9 * 16.0 / 9.0;
+        assert!((lights.pdf_value(Point3::zero(), up) - expected).abs() < 1e-9);
+        assert_eq!(lights.pdf_value(Point3::zero(), -up), 0.0);
+    }
+}
\ No newline at end of file
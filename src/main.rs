@@ -1,4 +1,6 @@
 pub mod prelude;
+pub mod rng;
+pub mod sampler;
 pub mod vec3;
 pub mod color;
 pub mod ray;
@@ -7,118 +9,397 @@ pub mod sphere;
 pub mod hittable_list;
 pub mod interval;
 pub mod camera;
+pub mod background;
 pub mod material;
+pub mod aabb;
+pub mod bvh;
+pub mod quad;
+pub mod triangle;
+pub mod transform;
+pub mod instance;
+pub mod animation;
+pub mod medium;
+pub mod grid;
+pub mod disk;
+pub mod mesh;
+pub mod obj;
+pub mod texture;
+pub mod perlin;
+pub mod onb;
+pub mod pdf;
+pub mod lights;
+pub mod output;
+pub mod framebuffer;
+pub mod scene;
+pub mod export;
+pub mod presets;
 
-use std::{fs::File, io::BufWriter, sync::Arc};
+use std::{fs::File, io::{self, BufWriter, Write}, path::Path, time::Instant};
 
 use crate::prelude::*;
 
-use clap::Parser;
-use material::{Dielectric, Lambertian, Metal};
-use sphere::Sphere;
+use bvh::{BvhNode, SplitMethod};
+use camera::MisHeuristic;
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use output::ImageFormat;
+use export::save_scene;
+use lights::LightSelection;
+use sampler::SamplerKind;
+use scene::{load_scene, Scene};
+use vec3::cross;
 
-/// Simple program to greet a person
+/// Path traces a built-in preset or a TOML scene file and writes out the rendered image
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Output file
+    /// Output file, or `-` for stdout; the format is chosen from its extension (ppm, png, bmp,
+    /// tga, exr, hdr, pfm)
     #[arg(short, long, default_value_t = String::from("image.ppm"))]
     output: String,
+
+    /// Image format, overriding the output file's extension
+    #[arg(long)]
+    format: Option<String>,
+
+    /// Bits per channel, for formats that support more than one (png: 8 or 16, exr: 16 or 32)
+    #[arg(long)]
+    bit_depth: Option<u8>,
+
+    /// Write plain-text P3 instead of binary P6 for .ppm output
+    #[arg(long)]
+    ascii: bool,
+
+    /// TOML scene file to render instead of a built-in preset
+    #[arg(long, conflicts_with = "scene_preset")]
+    scene: Option<String>,
+
+    /// Built-in scene to render (see --list-scenes)
+    #[arg(long)]
+    scene_preset: Option<String>,
+
+    /// List the built-in scene presets and exit
+    #[arg(long)]
+    list_scenes: bool,
+
+    /// Also write a grayscale image of the samples taken per pixel to this file, white being
+    /// the maximum samples per pixel
+    #[arg(long)]
+    samples_output: Option<String>,
+
+    /// Also write the rendered scene to this TOML scene file
+    #[arg(long)]
+    export: Option<String>,
+
+    /// Seed for the random scene layout of presets and for the pixel samples; the same seed
+    /// renders the same image. Scene files may set their own; otherwise it is 0
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// How the light sampled at each diffuse bounce is chosen
+    #[arg(long, value_enum, default_value_t = LightSelection::Power)]
+    light_selection: LightSelection,
+
+    /// How light samples and scattered rays are weighted against each other; power unless the
+    /// scene file says otherwise
+    #[arg(long, value_enum)]
+    mis: Option<MisHeuristic>,
+
+    /// How the random numbers of the pixel samples are generated; sobol unless the scene file
+    /// says otherwise
+    #[arg(long, value_enum)]
+    sampler: Option<SamplerKind>,
+
+    /// Acceleration structure built over the world before rendering
+    #[arg(long, value_enum, default_value_t = Accel::Sah)]
+    accel: Accel,
+
+    #[command(flatten)]
+    camera: CameraArgs,
+}
+
+/// Camera settings that override those of the scene file or preset.
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Camera")]
+struct CameraArgs {
+    /// Image width in pixels
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    width: Option<u32>,
+
+    /// Image height in pixels; the aspect ratio becomes width / height
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "aspect_ratio")]
+    height: Option<u32>,
+
+    /// Ratio of image width over height, as a number or `W:H`
+    #[arg(long, value_parser = parse_aspect_ratio)]
+    aspect_ratio: Option<f64>,
+
+    /// Random samples for each pixel, or the most any pixel takes with --adaptive-threshold
+    #[arg(long, visible_alias = "samples-per-pixel", value_parser = clap::value_parser!(u32).range(1..))]
+    spp: Option<u32>,
+
+    /// Stop sampling a pixel once the 95% confidence interval of its brightness is within this
+    /// fraction of it, e.g. 0.05
+    #[arg(long, value_parser = parse_positive)]
+    adaptive_threshold: Option<f64>,
+
+    /// Samples every pixel takes before adaptive sampling may stop it
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    min_spp: Option<u32>,
+
+    /// Maximum number of ray bounces into the scene
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    max_depth: Option<i32>,
+
+    /// Bounces before Russian roulette may end a path; at least max-depth disables it
+    #[arg(long)]
+    roulette_depth: Option<u32>,
+
+    /// Vertical field of view in degrees
+    #[arg(long, value_parser = parse_vfov)]
+    vfov: Option<f64>,
+
+    /// Point the camera is looking from, as `x,y,z`
+    #[arg(long, value_parser = parse_triple, allow_hyphen_values = true)]
+    look_from: Option<Point3>,
+
+    /// Point the camera is looking at, as `x,y,z`
+    #[arg(long, value_parser = parse_triple, allow_hyphen_values = true)]
+    look_at: Option<Point3>,
+
+    /// Camera-relative "up" direction, as `x,y,z`
+    #[arg(long, value_parser = parse_triple, allow_hyphen_values = true)]
+    vup: Option<Vec3>,
+
+    /// Variation angle of rays through each pixel in degrees; 0 disables defocus blur
+    #[arg(long, visible_alias = "defocus-angle", value_parser = parse_aperture)]
+    aperture: Option<f64>,
+
+    /// Distance from the camera to the plane of perfect focus
+    #[arg(long, value_parser = parse_positive)]
+    focus_dist: Option<f64>,
+
+    /// Time the shutter opens, from 0 to 1; motion blur covers the time until it closes
+    #[arg(long, value_parser = parse_unit_time)]
+    shutter_open: Option<f64>,
+
+    /// Time the shutter closes, from 0 to 1; equal to --shutter-open for a frozen instant
+    #[arg(long, value_parser = parse_unit_time)]
+    shutter_close: Option<f64>,
+}
+
+impl CameraArgs {
+    /// Overrides the camera fields given on the command line and recomputes the viewport.
+    fn apply(&self, cam: &mut Camera) -> Result<(), String> {
+        if let Some(width) = self.width { cam.image_width = width; }
+        if let Some(aspect_ratio) = self.aspect_ratio { cam.aspect_ratio = aspect_ratio; }
+        if let Some(height) = self.height {
+            // The camera truncates the height it derives, so nudge the ratio down when the
+            // division comes out just short of the requested height.
+            cam.aspect_ratio = cam.image_width as f64 / height as f64;
+            if ((cam.image_width as f64 / cam.aspect_ratio) as u32) < height {
+                cam.aspect_ratio = cam.aspect_ratio.next_down();
+            }
+        }
+        if let Some(spp) = self.spp { cam.samples_per_pixel = spp; }
+        if let Some(threshold) = self.adaptive_threshold { cam.adaptive_threshold = Some(threshold); }
+        if let Some(min_spp) = self.min_spp { cam.min_samples_per_pixel = min_spp; }
+        if let Some(max_depth) = self.max_depth { cam.max_depth = max_depth; }
+        if let Some(roulette_depth) = self.roulette_depth { cam.roulette_depth = roulette_depth; }
+        if let Some(vfov) = self.vfov { cam.vfov = vfov; }
+        if let Some(look_from) = self.look_from { cam.look_from = look_from; }
+        if let Some(look_at) = self.look_at { cam.look_at = look_at; }
+        if let Some(vup) = self.vup { cam.vup = vup; }
+        if let Some(aperture) = self.aperture { cam.defocus_angle = aperture; }
+        if let Some(focus_dist) = self.focus_dist { cam.focus_dist = focus_dist; }
+        if let Some(shutter_open) = self.shutter_open { cam.shutter_open = shutter_open; }
+        if let Some(shutter_close) = self.shutter_close { cam.shutter_close = shutter_close; }
+
+        let view = cam.look_at - cam.look_from;
+        if view.near_zero() {
+            return Err("the camera's look-from and look-at points must differ".to_string());
+        }
+        if cross(view, cam.vup).near_zero() {
+            return Err("the camera's vup must not be parallel to its view direction".to_string());
+        }
+        if cam.shutter_close < cam.shutter_open {
+            return Err("the shutter must not close before it opens".to_string());
+        }
+
+        cam.initialize();
+        Ok(())
+    }
+}
+
+fn parse_number(s: &str) -> Result<f64, String> {
+    let value: f64 = s.trim().parse().map_err(|_| format!("`{s}` is not a number"))?;
+    if value.is_finite() { Ok(value) } else { Err(format!("`{s}` is not finite")) }
+}
+
+fn parse_positive(s: &str) -> Result<f64, String> {
+    let value = parse_number(s)?;
+    if value > 0.0 { Ok(value) } else { Err("must be positive".to_string()) }
+}
+
+fn parse_aspect_ratio(s: &str) -> Result<f64, String> {
+    match s.split_once(':') {
+        Some((w, h)) => Ok(parse_positive(w)? / parse_positive(h)?),
+        None => parse_positive(s),
+    }
+}
+
+fn parse_vfov(s: &str) -> Result<f64, String> {
+    let value = parse_number(s)?;
+    if value > 0.0 && value < 180.0 {
+        Ok(value)
+    } else {
+        Err("must be between 0 and 180 degrees".to_string())
+    }
+}
+
+fn parse_aperture(s: &str) -> Result<f64, String> {
+    let value = parse_number(s)?;
+    if (0.0..180.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err("must be at least 0 and less than 180 degrees".to_string())
+    }
+}
+
+fn parse_unit_time(s: &str) -> Result<f64, String> {
+    let value = parse_number(s)?;
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err("must be between 0 and 1".to_string())
+    }
+}
+
+fn parse_triple(s: &str) -> Result<Vec3, String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    let [x, y, z] = parts[..] else {
+        return Err(format!("expected three comma-separated numbers, got `{s}`"));
+    };
+    Ok(Vec3::new(parse_number(x)?, parse_number(y)?, parse_number(z)?))
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Accel {
+    /// Test every object for every ray
+    None,
+    /// BVH split at the midpoint of the longest axis
+    Midpoint,
+    /// BVH split using the surface area heuristic
+    Sah,
 }
 
 fn main() -> std::io::Result<()> {
     
     let args = Args::parse();
-    let file = File::create(&args.output)?;
-    let mut writer = BufWriter::new(file);
-
-    // World
-
-    let mut world = HittableList::new();
-
-    let ground_material = 
-        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    world.objects.push(
-        Box::new(Sphere::stationary(
-            Point3::new(0.0, -1000.0, 0.0), 
-            1000.0, 
-            ground_material
-        ))
-    );
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat: f64 = random();
-            let center = Point3::new(
-                 a as f64 + 0.9 * random::<f64>(),
-                 0.2,
-                 b as f64 + 0.9 * random::<f64>(),
-            );
-
-            if (center - Point3::new(4.0, 0.2, 0.0)).len() > 0.9 {
-                if choose_mat < 0.8 {
-                    // diffuse
-                    let albedo = Color::random() * Color::random();
-                    let sphere_material = 
-                        Arc::new(Lambertian::new(albedo));
-                    let center2 = center 
-                        + Vec3::new(0.0, random_range(0.0, 0.5), 0.0);
-                    world.objects.push(Box::new(
-                        Sphere::moving(center, center2, 0.2, sphere_material)
-                    ));
-                } else if choose_mat < 0.95 {
-                    // metal
-                    let albedo = Color::random_range(0.5, 1.0);
-                    let fuzz = random_range(0.0, 0.5);
-                    let sphere_material = Arc::new(Metal::new(albedo, fuzz));
-                    world.objects.push(Box::new(
-                        Sphere::stationary(center, 0.2, sphere_material)
-                    ));
-                } else {
-                    // glass
-                    let sphere_material = 
-                        Arc::new(Dielectric::new(1.5));
-                    world.objects.push(Box::new(
-                        Sphere::stationary(center, 0.2, sphere_material)
-                    ));
-                }
+    if args.list_scenes {
+        for preset in presets::PRESETS {
+            println!("{:<20}{}", preset.name, preset.description);
+        }
+        return Ok(());
+    }
 
-            }
+    let format = match &args.format {
+        Some(extension) => ImageFormat::from_extension(extension, args.bit_depth, args.ascii),
+        None => ImageFormat::from_path(Path::new(&args.output), args.bit_depth, args.ascii),
+    }.unwrap_or_else(|e| Args::command().error(ErrorKind::InvalidValue, e).exit());
+    let samples_format = args.samples_output.as_ref().map(|path| {
+        ImageFormat::from_path(Path::new(path), None, false)
+            .unwrap_or_else(|e| Args::command().error(ErrorKind::InvalidValue, e).exit())
+    });
+
+    let Scene { camera: mut cam, world, mut lights } = match &args.scene {
+        Some(path) => load_scene(path).unwrap_or_else(|e| {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }),
+        None => {
+            let name = args.scene_preset.as_deref().unwrap_or(presets::DEFAULT_PRESET);
+            let preset = presets::find(name).unwrap_or_else(|| {
+                let names: Vec<_> = presets::PRESETS.iter().map(|p| p.name).collect();
+                let message = format!("unknown scene preset `{name}` (expected one of: {})", names.join(", "));
+                Args::command().error(ErrorKind::InvalidValue, message).exit()
+            });
+            (preset.build)(&mut rng::seeded(args.seed.unwrap_or(0)))
+        },
+    };
+
+    if let Some(seed) = args.seed { cam.seed = seed; }
+    if let Some(mis) = args.mis { cam.mis = mis; }
+    if let Some(sampler) = args.sampler { cam.sampler = sampler; }
+    lights.set_selection(args.light_selection);
+    if let Err(e) = args.camera.apply(&mut cam) {
+        Args::command().error(ErrorKind::ValueValidation, e).exit();
+    }
+
+    if let Some(path) = &args.export {
+        if let Err(e) = save_scene(path, &cam, &world) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
         }
     }
 
-    let mat1 = Arc::new(Dielectric::new(1.5));
-    world.objects.push(Box::new(
-        Sphere::stationary(Point3::new(0.0, 1.0, 0.0), 1.0, mat1)
-    ));
-
-    let mat2 = 
-        Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    world.objects.push(Box::new(
-        Sphere::stationary(Point3::new(-4.0, 1.0, 0.0), 1.0, mat2)
-    ));
-
-    let mat3 = 
-        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.0));
-    world.objects.push(Box::new(
-        Sphere::stationary(Point3::new(4.0, 1.0, 0.0), 1.0, mat3)
-    ));
-
-    let cam = Camera::new(
-        16.0 / 9.0,
-        400,
-        100,
-        50,
-        20.0,
-        Point3::new(13.0, 2.0, 3.0),
-        Point3::zero(),
-        Vec3::new(0.0, 1.0, 0.0),
-        0.6,
-        10.0
-    );
-
-    cam.render(&world, &mut writer)?;
+    let build_start = Instant::now();
+    let world: Box<dyn Hittable> = match args.accel {
+        Accel::None => Box::new(world),
+        Accel::Midpoint => Box::new(BvhNode::new(world, SplitMethod::Midpoint)),
+        Accel::Sah => Box::new(BvhNode::new(world, SplitMethod::Sah)),
+    };
+    eprintln!("Build ({:?}): {:.2?}", args.accel, build_start.elapsed());
+
+    let render_start = Instant::now();
+    let render = cam.render(world.as_ref(), &lights);
+    eprintln!("Render: {:.2?}", render_start.elapsed());
+
+    if cam.adaptive_threshold.is_some() {
+        let total: u64 = render.samples.iter().map(|&n| n as u64).sum();
+        eprintln!("Samples per pixel: {:.1} on average", total as f64 / render.samples.len() as f64);
+    }
+
+    if let Some(path) = &args.samples_output {
+        let format = samples_format.expect("format is checked before rendering");
+        let mut writer = BufWriter::new(File::create(path)?);
+        format.encode(&render.samples_image(), &mut writer)?;
+        writer.flush()?;
+    }
+
+    let output: Box<dyn Write> = if args.output == "-" {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(File::create(&args.output)?)
+    };
+    let mut writer = BufWriter::new(output);
+    format.encode(&render.image, &mut writer)?;
+    writer.flush()?;
     
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn height_override_gives_exactly_that_many_rows() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let look_from = Point3::new(0.0, 0.0, 1.0);
+        for height in 1..=300 {
+            let height_arg = height.to_string();
+            let args = Args::try_parse_from(["ray-tracing", "--width", "401", "--height", &height_arg])
+                .unwrap();
+            let mut cam = Camera::new(1.0, 100, 1, 5, 90.0, look_from, Point3::zero(), up, 0.0, 1.0);
+            args.camera.apply(&mut cam).unwrap();
+            assert_eq!((401.0 / cam.aspect_ratio) as u32, height);
+        }
+    }
+
+    #[test]
+    fn shutter_times_must_lie_between_zero_and_one() {
+        assert!(Args::try_parse_from(["ray-tracing", "--shutter-close", "0.5"]).is_ok());
+        assert!(Args::try_parse_from(["ray-tracing", "--shutter-close", "1.5"]).is_err());
+        assert!(Args::try_parse_from(["ray-tracing", "--shutter-open", "-0.5"]).is_err());
+    }
+}
\ No newline at end of file
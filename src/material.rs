@@ -1,59 +1,112 @@
-use vec3::{random_unit_vector, reflect, refract};
+use std::{f64::consts::PI, sync::Arc};
+
+use vec3::{reflect, refract};
 
 use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::pdf::{henyey_greenstein, CosinePdf, HenyeyGreensteinPdf, Pdf, SpherePdf};
+use crate::sampler::sample_uniform_sphere;
+use crate::scene::MaterialDescription;
+use crate::texture::{SolidColor, Texture};
 
 pub struct ScatterRecord {
     pub attenuation: Color,
-    pub scattered: Ray,
+    pub kind: ScatterKind,
+}
+
+pub enum ScatterKind {
+    /// The scattered direction is drawn from a distribution. The caller may sample it together
+    /// with the lights and weight the result using `Material::scattering_pdf`.
+    Pdf(Box<dyn Pdf>),
+    /// The scattered ray is fully determined by the material, as with mirrors and glass.
+    Specular(Ray),
 }
 
 pub trait Material: Sync + Send {
     fn scatter(
         &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-    ) -> Option<ScatterRecord>;
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        None
+    }
+
+    /// The density of the material scattering `r_in` into `scattered` at the hit point.
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    /// Radiance given off by the surface at the hit point. Most materials emit nothing.
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        Color::zero()
+    }
+
+    /// Whether this is the phase function of a participating medium rather than a surface.
+    /// Shadow rays pass through media, so light given off inside one is never found by sampling
+    /// lights.
+    fn is_phase_function(&self) -> bool {
+        false
+    }
+
+    /// The scene file description of this material. Materials that have none can leave this
+    /// out, and exporting a scene using them fails.
+    fn describe(&self, _scene: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        Err(ExportError::unsupported("this kind of material"))
+    }
 }
 
 pub struct Lambertian {
-    albedo: Color,
+    tex: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
-        Self { albedo }
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>) -> Self {
+        Self { tex }
     }
 }
 
 impl Material for Lambertian {
     fn scatter(
             &self,
-            r_in: &Ray,
+            _r_in: &Ray,
             rec: &HitRecord,
+            _sampler: &mut dyn Sampler,
         ) -> Option<ScatterRecord> 
     {
-        let mut scatter_direction = rec.normal + random_unit_vector();
-
-        if scatter_direction.near_zero() {
-            scatter_direction = rec.normal;
-        }
-
         Some(ScatterRecord {
-            attenuation: self.albedo,
-            scattered: Ray::new(rec.p, scatter_direction, r_in.time),
+            attenuation: self.tex.value(rec.u, rec.v, &rec.p),
+            kind: ScatterKind::Pdf(Box::new(CosinePdf::new(rec.normal))),
         })
     }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = dot(rec.normal, scattered.direction.unit_vector());
+        f64::max(0.0, cos_theta / PI)
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        Ok(MaterialDescription::Lambertian { albedo: scene.texture(&self.tex)? })
+    }
 }
 
 pub struct Metal {
-    albedo: Color,
+    tex: Arc<dyn Texture>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>, fuzz: f64) -> Self {
         let fuzz = f64::min(1.0, fuzz);
-        Self { albedo, fuzz }
+        Self { tex, fuzz }
     }
 }
 
@@ -62,22 +115,27 @@ impl Material for Metal {
             &self,
             r_in: &Ray,
             rec: &HitRecord,
+            sampler: &mut dyn Sampler,
         ) -> Option<ScatterRecord> 
     {
         let reflected = reflect(r_in.direction.unit_vector(), rec.normal);
-        let reflected = reflected.unit_vector() + (self.fuzz * random_unit_vector());
+        let reflected = reflected.unit_vector() + (self.fuzz * sample_uniform_sphere(sampler.get_2d()));
         
         let scattered = Ray::new(rec.p, reflected, r_in.time);
 
         if dot(scattered.direction, rec.normal) > 0.0 {
             Some(ScatterRecord {
-                attenuation: self.albedo,
-                scattered,
+                attenuation: self.tex.value(rec.u, rec.v, &rec.p),
+                kind: ScatterKind::Specular(scattered),
             })
         } else {
             None
         }
     }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        Ok(MaterialDescription::Metal { albedo: scene.texture(&self.tex)?, fuzz: self.fuzz })
+    }
 }
 
 pub struct Dielectric {
@@ -101,6 +159,7 @@ impl Material for Dielectric {
             &self,
             r_in: &Ray,
             rec: &HitRecord,
+            sampler: &mut dyn Sampler,
         ) -> Option<ScatterRecord> {
         let ri = if rec.front_face { 
             1.0 / self.refraction_index 
@@ -112,9 +171,10 @@ impl Material for Dielectric {
         let cos_theta = f64::min(dot(-unit_direction, rec.normal), 1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
         let cannot_refract = ri * sin_theta > 1.0;
+        let u = sampler.get_1d();
 
         let direction = if cannot_refract 
-            || Self::reflectance(cos_theta, ri) > random()
+            || Self::reflectance(cos_theta, ri) > u
         {
             reflect(unit_direction, rec.normal)
         } else {
@@ -123,7 +183,165 @@ impl Material for Dielectric {
 
         Some(ScatterRecord {
             attenuation: Color::new(1.0, 1.0, 1.0),
-            scattered: Ray::new(rec.p, direction, r_in.time),
+            kind: ScatterKind::Specular(Ray::new(rec.p, direction, r_in.time)),
         })
     }
+
+    fn describe(&self, _scene: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        Ok(MaterialDescription::Dielectric { refraction_index: self.refraction_index })
+    }
+}
+
+
+/// Emits the radiance given by its texture and never scatters.
+pub struct DiffuseLight {
+    tex: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>) -> Self {
+        Self { tex }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn emitted(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        self.tex.value(rec.u, rec.v, &rec.p)
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        Ok(MaterialDescription::DiffuseLight { emit: scene.texture(&self.tex)? })
+    }
+}
+
+/// Scatters equally in every direction. This is the phase function of a participating medium
+/// such as `ConstantMedium`, rather than a surface material.
+pub struct Isotropic {
+    tex: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>) -> Self {
+        Self { tex }
+    }
 }
+
+impl Material for Isotropic {
+    fn scatter(
+            &self,
+            _r_in: &Ray,
+            rec: &HitRecord,
+            _sampler: &mut dyn Sampler,
+        ) -> Option<ScatterRecord>
+    {
+        Some(ScatterRecord {
+            attenuation: self.tex.value(rec.u, rec.v, &rec.p),
+            kind: ScatterKind::Pdf(Box::new(SpherePdf)),
+        })
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn is_phase_function(&self) -> bool {
+        true
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        Ok(MaterialDescription::Isotropic { albedo: scene.texture(&self.tex)? })
+    }
+}
+
+/// A phase function for media that scatter light mostly forwards, like clouds, or mostly back,
+/// following Henyey and Greenstein. `g` is the average cosine of the scattering angle, strictly
+/// between -1 and 1; zero scatters evenly like `Isotropic`.
+pub struct HenyeyGreenstein {
+    tex: Arc<dyn Texture>,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), g)
+    }
+
+    pub fn from_texture(tex: Arc<dyn Texture>, g: f64) -> Self {
+        Self { tex, g }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+            &self,
+            r_in: &Ray,
+            rec: &HitRecord,
+            _sampler: &mut dyn Sampler,
+        ) -> Option<ScatterRecord>
+    {
+        Some(ScatterRecord {
+            attenuation: self.tex.value(rec.u, rec.v, &rec.p),
+            kind: ScatterKind::Pdf(Box::new(HenyeyGreensteinPdf::new(r_in.direction, self.g))),
+        })
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = dot(r_in.direction.unit_vector(), scattered.direction.unit_vector());
+        henyey_greenstein(cos_theta, self.g)
+    }
+
+    fn is_phase_function(&self) -> bool {
+        true
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        Ok(MaterialDescription::HenyeyGreenstein { albedo: scene.texture(&self.tex)?, g: self.g })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::IndependentSampler;
+    use crate::texture::UvCheckerTexture;
+
+    fn hit_at(p: Point3, mat: Arc<dyn Material>) -> HitRecord {
+        HitRecord { p, normal: Vec3::new(0.0, 1.0, 0.0), mat, t: 1.0, u: 0.25, v: 0.75, front_face: true }
+    }
+
+    #[test]
+    fn diffuse_lights_glow_and_never_scatter() {
+        let light: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(4.0, 2.0, 1.0)));
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = hit_at(Point3::zero(), light.clone());
+
+        assert_eq!(light.emitted(&r, &rec).x, 4.0);
+        assert!(light.scatter(&r, &rec, &mut IndependentSampler::new(1)).is_none());
+    }
+
+    #[test]
+    fn lights_take_their_glow_from_a_texture() {
+        let checker = Arc::new(UvCheckerTexture::from_colors(
+            2.0, 2.0, Color::new(1.0, 1.0, 1.0), Color::zero(),
+        ));
+        let light: Arc<dyn Material> = Arc::new(DiffuseLight::from_texture(checker));
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        // u = 0.25 and v = 0.75 fall in squares 0 and 1, an odd square.
+        assert_eq!(light.emitted(&r, &hit_at(Point3::zero(), light.clone())).len_squared(), 0.0);
+    }
+
+    #[test]
+    fn surfaces_emit_nothing() {
+        let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert_eq!(mat.emitted(&r, &hit_at(Point3::zero(), mat.clone())).len_squared(), 0.0);
+    }
+}
\ No newline at end of file
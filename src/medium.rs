@@ -0,0 +1,383 @@
+use std::sync::Arc;
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::grid::DensityGrid;
+use crate::material::{Isotropic, ScatterRecord};
+use crate::rng;
+use crate::sampler::IndependentSampler;
+use crate::scene::{EmissionDescription, GridDescription, ObjectDescription};
+
+/// The part of `ray_t` where `r` is inside `boundary`, found from where the ray enters and
+/// leaves it. Rays starting inside count as having entered. The boundary should be convex, since
+/// only its first two crossings are considered.
+fn inside(boundary: &dyn Hittable, r: &Ray, ray_t: Interval) -> Option<Interval> {
+    let rec1 = boundary.hit_surface(r, Interval::universe())?;
+    let rec2 = boundary.hit_surface(r, Interval::new(rec1.t + 0.0001, f64::INFINITY))?;
+
+    let span = Interval::new(rec1.t.max(ray_t.min).max(0.0), rec2.t.min(ray_t.max));
+    (span.min < span.max).then_some(span)
+}
+
+/// A scattering event at `t` along `r`. Media have no surface, so the normal is arbitrary.
+fn collision(r: &Ray, t: f64, mat: Arc<dyn Material>) -> HitRecord {
+    HitRecord {
+        p: r.at(t),
+        normal: Vec3::new(1.0, 0.0, 0.0), // arbitrary
+        mat,
+        t,
+        u: 0.0,
+        v: 0.0,
+        front_face: true, // also arbitrary
+    }
+}
+
+/// The randoms for a walk through a medium, which takes a varying number of steps: the first
+/// comes from `sampler`, and the rest from a generator seeded by it. Every walk then uses one
+/// dimension of the sampler however long it runs, keeping later bounces on their dimensions.
+fn walk_randoms(sampler: &mut dyn Sampler) -> (f64, rng::Rng) {
+    let u = sampler.get_1d();
+    (u, rng::seeded(rng::hash(&[u.to_bits()])))
+}
+
+/// A volume of uniform density filling a closed boundary, such as smoke or fog. A ray passing
+/// through it scatters after a random, exponentially distributed distance, which is more
+/// likely the denser the medium and the longer the path inside.
+pub struct ConstantMedium<H: Hittable> {
+    boundary: H,
+    density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl<H: Hittable> ConstantMedium<H> {
+    /// Fills `boundary` with a medium scattering light according to `phase_function`.
+    pub fn new(boundary: H, density: f64, phase_function: Arc<dyn Material>) -> Self {
+        Self { boundary, density, phase_function }
+    }
+
+    /// Fills `boundary` with a medium scattering the fraction `albedo` of light evenly in
+    /// every direction.
+    pub fn isotropic(boundary: H, density: f64, albedo: Color) -> Self {
+        Self::new(boundary, density, Arc::new(Isotropic::new(albedo)))
+    }
+}
+
+impl<H: Hittable> Hittable for ConstantMedium<H> {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.hit_sampled(r, ray_t, &mut IndependentSampler::for_ray(r))
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let span = inside(&self.boundary, r, ray_t)?;
+
+        let ray_length = r.direction.len();
+        let distance_inside_boundary = span.len() * ray_length;
+        let hit_distance = -(1.0 - sampler.get_1d()).ln() / self.density;
+        if hit_distance > distance_inside_boundary { return None; }
+
+        Some(collision(r, span.min + hit_distance / ray_length, self.phase_function.clone()))
+    }
+
+    fn hit_surface(&self, _r: &Ray, _ray_t: Interval) -> Option<HitRecord> {
+        None
+    }
+
+    /// Beer's law: light falls off exponentially with the distance travelled inside.
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        match inside(&self.boundary, r, ray_t) {
+            Some(span) => (-self.density * span.len() * r.direction.len()).exp(),
+            None => 1.0,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let boundary = scene.capture(|scene| self.boundary.describe(scene))?;
+        let material = scene.material(&self.phase_function)?;
+        scene.push_object(ObjectDescription::ConstantMedium {
+            boundary,
+            density: self.density,
+            material,
+        });
+        Ok(())
+    }
+}
+
+/// A volume whose density varies from place to place, given by a voxel grid stretched over
+/// `bounds`, such as smoke or fire from a simulation. Collisions are found by delta tracking:
+/// the ray takes exponentially distributed steps as if the whole grid were as dense as its
+/// densest voxel, and at each stop collides with probability of the actual density over that
+/// maximum. Shadow rays estimate the light getting through by ratio tracking, which multiplies
+/// those probabilities of passing instead of drawing a yes or no answer.
+pub struct GridMedium<H: Hittable> {
+    boundary: H,
+    grid: Arc<DensityGrid>,
+    /// Scales the grid's values into extinction per unit length.
+    density: f64,
+    bounds: Aabb,
+    phase_function: Arc<dyn Material>,
+    emission: Option<(Arc<DensityGrid>, Color)>,
+}
+
+impl<H: Hittable> GridMedium<H> {
+    /// Fills `boundary` with a medium whose extinction is `density` times the value of `grid`,
+    /// stretched over the boundary's bounding box.
+    pub fn new(
+        boundary: H,
+        grid: Arc<DensityGrid>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        let bounds = boundary.bounding_box();
+        Self { boundary, grid, density, bounds, phase_function, emission: None }
+    }
+
+    /// Stretches the grid over `bounds` instead of the boundary's bounding box. The medium is
+    /// empty where the boundary reaches past the grid.
+    pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Makes the medium glow, for flames. Every collision gives off `color` times the value of
+    /// `grid`, which is laid over the same bounds as the density grid, so only regions that
+    /// have some density can be seen to glow.
+    pub fn with_emission(mut self, grid: Arc<DensityGrid>, color: Color) -> Self {
+        self.emission = Some((grid, color));
+        self
+    }
+
+    /// Where `p` falls in the grid, with the grid spanning the unit cube.
+    fn grid_point(&self, p: Point3) -> Point3 {
+        let local = |axis: usize| {
+            let range = self.bounds.axis_interval(axis);
+            if range.len() > 0.0 { (p[axis] - range.min) / range.len() } else { 0.5 }
+        };
+        Point3::new(local(0), local(1), local(2))
+    }
+
+    /// The phase function at a collision at `p`, given off light included.
+    fn collision_material(&self, p: Point3) -> Arc<dyn Material> {
+        let Some((grid, color)) = &self.emission else { return self.phase_function.clone(); };
+
+        let radiance = *color * grid.value(self.grid_point(p));
+        if radiance.len_squared() == 0.0 { return self.phase_function.clone(); }
+        Arc::new(Glowing { phase_function: self.phase_function.clone(), radiance })
+    }
+}
+
+impl<H: Hittable> Hittable for GridMedium<H> {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.hit_sampled(r, ray_t, &mut IndependentSampler::for_ray(r))
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let span = inside(&self.boundary, r, ray_t)?;
+        let majorant = self.density * self.grid.max();
+        if majorant <= 0.0 { return None; }
+
+        let (mut u, mut rng) = walk_randoms(sampler);
+        let step = 1.0 / (majorant * r.direction.len());
+        let mut t = span.min;
+        loop {
+            t -= (1.0 - u).ln() * step;
+            u = rng.gen();
+            if t >= span.max { return None; }
+
+            let p = r.at(t);
+            if rng.gen::<f64>() * majorant < self.density * self.grid.value(self.grid_point(p)) {
+                return Some(collision(r, t, self.collision_material(p)));
+            }
+        }
+    }
+
+    fn hit_surface(&self, _r: &Ray, _ray_t: Interval) -> Option<HitRecord> {
+        None
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        self.transmittance_sampled(r, ray_t, &mut IndependentSampler::for_ray(r))
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        let Some(span) = inside(&self.boundary, r, ray_t) else { return 1.0; };
+        let majorant = self.density * self.grid.max();
+        if majorant <= 0.0 { return 1.0; }
+
+        let (mut u, mut rng) = walk_randoms(sampler);
+        let step = 1.0 / (majorant * r.direction.len());
+        let mut transmittance = 1.0;
+        let mut t = span.min;
+        loop {
+            t -= (1.0 - u).ln() * step;
+            u = rng.gen();
+            if t >= span.max { return transmittance; }
+
+            let density = self.density * self.grid.value(self.grid_point(r.at(t)));
+            transmittance *= 1.0 - density / majorant;
+
+            // Russian roulette stops tracking rays that are almost fully blocked.
+            if transmittance < 0.1 {
+                if rng.gen::<f64>() >= 0.5 { return 0.0; }
+                transmittance *= 2.0;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let source = |grid: &DensityGrid| -> Result<GridDescription, ExportError> {
+            grid.source().cloned().ok_or_else(|| ExportError::unsupported("a grid built in code"))
+        };
+
+        let boundary = scene.capture(|scene| self.boundary.describe(scene))?;
+        let material = scene.material(&self.phase_function)?;
+        let emission = match &self.emission {
+            Some((grid, color)) => Some(EmissionDescription {
+                grid: if Arc::ptr_eq(grid, &self.grid) { None } else { Some(source(grid)?) },
+                color: (*color).into(),
+            }),
+            None => None,
+        };
+        let (x, y, z) = (self.bounds.x, self.bounds.y, self.bounds.z);
+        scene.push_object(ObjectDescription::GridMedium {
+            boundary,
+            grid: source(&self.grid)?,
+            density: self.density,
+            bounds: Some([[x.min, y.min, z.min], [x.max, y.max, z.max]]),
+            material,
+            emission,
+        });
+        Ok(())
+    }
+}
+
+/// The phase function at a collision in a glowing medium, together with the light given off
+/// there. Made afresh for every collision, and never exported.
+struct Glowing {
+    phase_function: Arc<dyn Material>,
+    radiance: Color,
+}
+
+impl Material for Glowing {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterRecord> {
+        self.phase_function.scatter(r_in, rec, sampler)
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.phase_function.scattering_pdf(r_in, rec, scattered)
+    }
+
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        self.radiance
+    }
+
+    fn is_phase_function(&self) -> bool {
+        self.phase_function.is_phase_function()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+
+    /// Hands out the same value for every dimension, counting how many were asked for.
+    struct Fixed {
+        value: f64,
+        used: usize,
+    }
+
+    impl Sampler for Fixed {
+        fn start_sample(&mut self, _x: u32, _y: u32, _index: u32) {}
+
+        fn get_1d(&mut self) -> f64 {
+            self.used += 1;
+            self.value
+        }
+
+        fn get_2d(&mut self) -> (f64, f64) {
+            (self.get_1d(), self.get_1d())
+        }
+    }
+
+    fn fixed(value: f64) -> Fixed {
+        Fixed { value, used: 0 }
+    }
+
+    /// A unit sphere at the origin, with a ray crossing two units of it along z.
+    fn ball() -> (Sphere, Ray) {
+        let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        (Sphere::stationary(Point3::zero(), 1.0, mat), ray)
+    }
+
+    fn uniform_grid(ball: Sphere, density: f64) -> GridMedium<Sphere> {
+        let grid = Arc::new(DensityGrid::new([2, 2, 2], vec![1.0; 8]).unwrap());
+        let phase: Arc<dyn Material> = Arc::new(Isotropic::new(Color::new(0.5, 0.5, 0.5)));
+        GridMedium::new(ball, grid, density, phase)
+    }
+
+    #[test]
+    fn constant_medium_collides_where_the_sampler_says() {
+        let (ball, ray) = ball();
+        let smoke = ConstantMedium::isotropic(ball, 1.0, Color::new(0.5, 0.5, 0.5));
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        let mut sampler = fixed(0.5);
+        let rec = smoke.hit_sampled(&ray, ray_t, &mut sampler).unwrap();
+        assert!((rec.t - (4.0 + f64::ln(2.0))).abs() < 1e-9);
+        assert_eq!(sampler.used, 1);
+
+        assert!(smoke.hit_sampled(&ray, ray_t, &mut fixed(0.99)).is_none());
+    }
+
+    #[test]
+    fn constant_medium_transmittance_follows_beers_law() {
+        let (ball, ray) = ball();
+        let smoke = ConstantMedium::isotropic(ball, 0.5, Color::new(0.5, 0.5, 0.5));
+        let transmittance = smoke.transmittance(&ray, Interval::new(0.001, f64::INFINITY));
+        assert!((transmittance - f64::exp(-1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn grid_walks_take_one_dimension_and_repeat_with_it() {
+        let (ball, ray) = ball();
+        let cloud = uniform_grid(ball, 3.0);
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        let mut sampler = fixed(0.1);
+        let first = cloud.hit_sampled(&ray, ray_t, &mut sampler).map(|rec| rec.t);
+        let second = cloud.hit_sampled(&ray, ray_t, &mut fixed(0.1)).map(|rec| rec.t);
+        assert_eq!(sampler.used, 1);
+        assert_eq!(first, second);
+
+        let mut sampler = fixed(0.1);
+        cloud.transmittance_sampled(&ray, ray_t, &mut sampler);
+        assert_eq!(sampler.used, 1);
+    }
+
+    #[test]
+    fn ratio_tracking_matches_beers_law_on_average() {
+        let (ball, ray) = ball();
+        let cloud = uniform_grid(ball, 0.5);
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        let mut sampler = IndependentSampler::new(7);
+        let n = 20_000;
+        let mean = (0..n)
+            .map(|i| {
+                sampler.start_sample(0, 0, i);
+                cloud.transmittance_sampled(&ray, ray_t, &mut sampler)
+            })
+            .sum::<f64>() / n as f64;
+        assert!((mean - f64::exp(-1.0)).abs() < 0.01, "mean transmittance {mean}");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,208 @@
+use std::{path::PathBuf, sync::Arc};
+
+use vec3::cross;
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::scene::ObjectDescription;
+use crate::bvh::{BvhNode, SplitMethod};
+use crate::triangle;
+
+/// One triangle of a mesh, as indices into the shared vertex buffers.
+#[derive(Debug, Clone, Copy)]
+pub struct MeshFace {
+    pub vertices: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub material: usize,
+}
+
+/// Vertex buffers and faces shared by every triangle of a mesh.
+#[derive(Default)]
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub faces: Vec<MeshFace>,
+    pub materials: Vec<Arc<dyn Material>>,
+}
+
+impl MeshData {
+    /// Gives every face without vertex normals the area-weighted average of the face normals
+    /// around each of its vertices, so the mesh shades smoothly across shared vertices. Faces
+    /// that already have normals keep them.
+    pub fn compute_smooth_normals(&mut self) {
+        let mut normals = vec![Vec3::zero(); self.positions.len()];
+        for face in &self.faces {
+            let [a, b, c] = face.vertices.map(|i| self.positions[i]);
+            // The cross product's length is twice the face area, which gives the weighting.
+            let n = cross(b - a, c - a);
+            for i in face.vertices {
+                normals[i] += n;
+            }
+        }
+
+        // The smoothed normals go after the authored ones, one per position.
+        let offset = self.normals.len();
+        self.normals.extend(normals.into_iter()
+            .map(|n| if n.near_zero() { n } else { n.unit_vector() }));
+        for face in self.faces.iter_mut().filter(|face| face.normals.is_none()) {
+            face.normals = Some(face.vertices.map(|i| offset + i));
+        }
+    }
+
+    fn face_bbox(&self, face: &MeshFace) -> Aabb {
+        let [a, b, c] = face.vertices.map(|i| self.positions[i]);
+        Aabb::from_boxes(&Aabb::from_points(a, b), &Aabb::from_points(b, c))
+    }
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+    bbox: Aabb,
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mesh = &self.mesh;
+        let face = &mesh.faces[self.face];
+        let [a, b, c] = face.vertices.map(|i| mesh.positions[i]);
+        let (t, b1, b2) = triangle::intersect(a, b, c, r, ray_t)?;
+
+        let mut rec = HitRecord {
+            p: r.at(t),
+            normal: Default::default(),
+            mat: mesh.materials[face.material].clone(),
+            t,
+            u: 0.0,
+            v: 0.0,
+            front_face: Default::default(),
+        };
+        let normals = face.normals.map(|ns| ns.map(|i| mesh.normals[i]));
+        let uvs = face.uvs.map(|ts| ts.map(|i| mesh.uvs[i]));
+        triangle::shade(&mut rec, r, cross(b - a, c - a).unit_vector(), normals, uvs, b1, b2);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+/// The OBJ file a mesh was loaded from, so it can be exported as a reference to that file.
+pub struct MeshSource {
+    pub path: PathBuf,
+    pub smooth_normals: bool,
+    pub default_material: Arc<dyn Material>,
+}
+
+/// Indexed triangle mesh. All triangles reference one set of vertex buffers and are traced
+/// through their own BVH.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+    bvh: BvhNode,
+    source: Option<MeshSource>,
+}
+
+impl TriangleMesh {
+    pub fn new(data: MeshData) -> Self {
+        let data = Arc::new(data);
+        let mut triangles = HittableList::new();
+        for (face, mesh_face) in data.faces.iter().enumerate() {
+            triangles.objects.push(Box::new(MeshTriangle {
+                mesh: data.clone(),
+                face,
+                bbox: data.face_bbox(mesh_face),
+            }));
+        }
+        let bvh = BvhNode::new(triangles, SplitMethod::Sah);
+        Self { data, bvh, source: None }
+    }
+
+    pub fn with_source(mut self, source: MeshSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn data(&self) -> &MeshData {
+        &self.data
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.bvh.hit(r, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+
+    /// Meshes loaded from a file are written as a reference to it; anything else is written
+    /// out triangle by triangle.
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        if let Some(source) = &self.source {
+            let material = scene.material(&source.default_material)?;
+            scene.push_object(ObjectDescription::Mesh {
+                path: source.path.clone(),
+                material,
+                smooth_normals: source.smooth_normals,
+            });
+            return Ok(());
+        }
+
+        let data = &self.data;
+        for face in &data.faces {
+            let material = scene.material(&data.materials[face.material])?;
+            scene.push_object(ObjectDescription::Triangle {
+                vertices: face.vertices.map(|i| data.positions[i].into()),
+                normals: face.normals.map(|ns| ns.map(|i| data.normals[i].into())),
+                uvs: face.uvs.map(|ts| ts.map(|i| [data.uvs[i].0, data.uvs[i].1])),
+                material,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    /// Two triangles folded along the x axis, the first with an authored normal.
+    fn folded() -> MeshData {
+        MeshData {
+            positions: vec![
+                Point3::zero(),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Point3::new(0.0, 0.0, -1.0),
+            ],
+            normals: vec![Vec3::new(0.0, 1.0, 1.0)],
+            uvs: Vec::new(),
+            faces: vec![
+                MeshFace { vertices: [0, 1, 2], normals: Some([0, 0, 0]), uvs: None, material: 0 },
+                MeshFace { vertices: [0, 1, 3], normals: None, uvs: None, material: 0 },
+            ],
+            materials: vec![Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))],
+        }
+    }
+
+    #[test]
+    fn smooth_normals_keep_authored_normals() {
+        let mut data = folded();
+        data.compute_smooth_normals();
+
+        assert_eq!(data.faces[0].normals, Some([0, 0, 0]));
+        assert!((data.normals[0] - Vec3::new(0.0, 1.0, 1.0)).len() < 1e-12);
+
+        let [n0, n1, n3] = data.faces[1].normals.unwrap().map(|i| data.normals[i]);
+        // Shared vertices average both faces; the unshared one only sees its own face.
+        let diagonal = Vec3::new(0.0, 1.0, 1.0).unit_vector();
+        assert!((n0 - diagonal).len() < 1e-12);
+        assert!((n1 - diagonal).len() < 1e-12);
+        assert!((n3 - Vec3::new(0.0, 1.0, 0.0)).len() < 1e-12);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs::File,
+    fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    str::SplitWhitespace,
+    sync::Arc,
+};
+
+use crate::prelude::*;
+use crate::material::{Dielectric, Lambertian, Metal};
+use crate::mesh::{MeshData, MeshFace, MeshSource, TriangleMesh};
+
+pub struct ObjOptions {
+    /// Computes area-weighted vertex normals for faces the file doesn't give normals.
+    pub smooth_normals: bool,
+    /// Used for faces that appear before any `usemtl` statement.
+    pub default_material: Arc<dyn Material>,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            ObjError::Parse { path, line, message } => {
+                write!(f, "{}:{line}: {message}", path.display())
+            },
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+/// Loads a Wavefront OBJ file, along with any MTL libraries it references, into a triangle mesh.
+/// Polygons are fan-triangulated.
+pub fn load_obj(path: impl AsRef<Path>, options: &ObjOptions) -> Result<TriangleMesh, ObjError> {
+    let path = path.as_ref();
+    let mut data = MeshData::default();
+    data.materials.push(options.default_material.clone());
+
+    let mut material_ids: HashMap<String, usize> = HashMap::new();
+    let mut current_material = 0;
+
+    for (line_idx, line) in open(path)?.lines().enumerate() {
+        let line = line.map_err(|source| ObjError::Io { path: path.to_path_buf(), source })?;
+        let parse_error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: line_idx + 1,
+            message,
+        };
+
+        let line = strip_comment(&line);
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue; };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(&mut tokens, "vertex position").map_err(parse_error)?;
+                data.positions.push(Point3::new(x, y, z));
+            },
+            "vn" => {
+                let [x, y, z] = parse_floats(&mut tokens, "vertex normal").map_err(parse_error)?;
+                data.normals.push(Vec3::new(x, y, z));
+            },
+            "vt" => {
+                let [u] = parse_floats(&mut tokens, "texture coordinate").map_err(parse_error)?;
+                let v = match tokens.next() {
+                    Some(token) => parse_float(token).map_err(parse_error)?,
+                    None => 0.0,
+                };
+                data.uvs.push((u, v));
+            },
+            "f" => {
+                let faces = parse_face(tokens, &data, current_material).map_err(parse_error)?;
+                data.faces.extend(faces);
+            },
+            "mtllib" => {
+                let name = line[keyword.len()..].trim();
+                if name.is_empty() {
+                    return Err(parse_error("mtllib requires a file name".into()));
+                }
+                let mtl_path = path.parent().unwrap_or(Path::new("")).join(name);
+                for (name, params) in load_mtl(&mtl_path)? {
+                    material_ids.insert(name, data.materials.len());
+                    data.materials.push(params.to_material());
+                }
+            },
+            "usemtl" => {
+                let name = line[keyword.len()..].trim();
+                current_material = *material_ids.get(name)
+                    .ok_or_else(|| parse_error(format!("unknown material '{name}'")))?;
+            },
+            // Groups, objects, smoothing groups and line/point elements don't affect the mesh.
+            _ => {},
+        }
+    }
+
+    if options.smooth_normals && data.faces.iter().any(|face| face.normals.is_none()) {
+        data.compute_smooth_normals();
+    }
+
+    let source = MeshSource {
+        path: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+        smooth_normals: options.smooth_normals,
+        default_material: options.default_material.clone(),
+    };
+    Ok(TriangleMesh::new(data).with_source(source))
+}
+
+/// Surface parameters collected from one `newmtl` block.
+struct MtlParams {
+    diffuse: Color,
+    /// Left unset by materials without a `Ks` statement.
+    specular: Option<Color>,
+    shininess: f64,
+    refraction_index: Option<f64>,
+    dissolve: f64,
+    illum: u32,
+}
+
+impl Default for MtlParams {
+    fn default() -> Self {
+        Self {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: None,
+            shininess: 0.0,
+            refraction_index: None,
+            dissolve: 1.0,
+            illum: 1,
+        }
+    }
+}
+
+impl MtlParams {
+    /// Maps the MTL illumination model onto the closest material we support: anything
+    /// transparent becomes glass, models with ray-traced reflection become metal, and everything
+    /// else is diffuse.
+    fn to_material(&self) -> Arc<dyn Material> {
+        if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            Arc::new(Dielectric::new(self.refraction_index.unwrap_or(1.5)))
+        } else if matches!(self.illum, 3 | 5 | 8) {
+            // Phong exponents run from 0 (rough) to 1000 (mirror-like).
+            let fuzz = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+            // Without a specular color, the metal takes on the diffuse one.
+            Arc::new(Metal::new(self.specular.unwrap_or(self.diffuse), fuzz))
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        }
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<Vec<(String, MtlParams)>, ObjError> {
+    let mut materials = Vec::new();
+    let mut current: Option<(String, MtlParams)> = None;
+
+    for (line_idx, line) in open(path)?.lines().enumerate() {
+        let line = line.map_err(|source| ObjError::Io { path: path.to_path_buf(), source })?;
+        let parse_error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: line_idx + 1,
+            message,
+        };
+
+        let line = strip_comment(&line);
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue; };
+
+        if keyword == "newmtl" {
+            let name = line[keyword.len()..].trim();
+            if name.is_empty() {
+                return Err(parse_error("newmtl requires a material name".into()));
+            }
+            materials.extend(current.take());
+            current = Some((name.to_string(), MtlParams::default()));
+            continue;
+        }
+
+        let Some((_, params)) = current.as_mut() else {
+            return Err(parse_error(format!("'{keyword}' before any newmtl statement")));
+        };
+
+        match keyword {
+            "Kd" => {
+                let [r, g, b] = parse_floats(&mut tokens, "diffuse color").map_err(parse_error)?;
+                params.diffuse = Color::new(r, g, b);
+            },
+            "Ks" => {
+                let [r, g, b] = parse_floats(&mut tokens, "specular color").map_err(parse_error)?;
+                params.specular = Some(Color::new(r, g, b));
+            },
+            "Ns" => {
+                [params.shininess] = parse_floats(&mut tokens, "specular exponent")
+                    .map_err(parse_error)?;
+            },
+            "Ni" => {
+                let [ni] = parse_floats(&mut tokens, "refraction index").map_err(parse_error)?;
+                params.refraction_index = Some(ni);
+            },
+            "d" => {
+                [params.dissolve] = parse_floats(&mut tokens, "dissolve").map_err(parse_error)?;
+            },
+            "Tr" => {
+                let [tr] = parse_floats(&mut tokens, "transparency").map_err(parse_error)?;
+                params.dissolve = 1.0 - tr;
+            },
+            "illum" => {
+                let token = tokens.next()
+                    .ok_or_else(|| parse_error("missing illumination model".into()))?;
+                params.illum = token.parse()
+                    .map_err(|_| parse_error(format!("invalid illumination model '{token}'")))?;
+            },
+            // Texture maps and the remaining lighting terms have no equivalent here.
+            _ => {},
+        }
+    }
+
+    materials.extend(current);
+    Ok(materials)
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, ObjError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|source| ObjError::Io { path: path.to_path_buf(), source })
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split('#').next().unwrap_or("").trim()
+}
+
+fn parse_float(token: &str) -> Result<f64, String> {
+    token.parse().map_err(|_| format!("invalid number '{token}'"))
+}
+
+fn parse_floats<const N: usize>(
+    tokens: &mut SplitWhitespace,
+    what: &str,
+) -> Result<[f64; N], String> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        let token = tokens.next()
+            .ok_or_else(|| format!("{what} needs {N} component(s)"))?;
+        *value = parse_float(token)?;
+    }
+    Ok(values)
+}
+
+/// Resolves a 1-based (or negative, relative) OBJ index against the number of elements defined
+/// so far.
+fn resolve_index(token: &str, count: usize, what: &str) -> Result<usize, String> {
+    let idx: i64 = token.parse().map_err(|_| format!("invalid {what} index '{token}'"))?;
+    let resolved = if idx < 0 { count as i64 + idx } else { idx - 1 };
+    if idx == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{what} index {idx} out of range (have {count})"));
+    }
+    Ok(resolved as usize)
+}
+
+/// A single `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner.
+struct Corner {
+    vertex: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+fn parse_corner(token: &str, data: &MeshData) -> Result<Corner, String> {
+    let mut parts = token.split('/');
+    let vertex = resolve_index(parts.next().unwrap_or(""), data.positions.len(), "vertex")?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(t) => Some(resolve_index(t, data.uvs.len(), "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(t) => Some(resolve_index(t, data.normals.len(), "normal")?),
+    };
+    if parts.next().is_some() {
+        return Err(format!("malformed face vertex '{token}'"));
+    }
+    Ok(Corner { vertex, uv, normal })
+}
+
+fn parse_face(
+    tokens: SplitWhitespace,
+    data: &MeshData,
+    material: usize,
+) -> Result<Vec<MeshFace>, String> {
+    let corners = tokens
+        .map(|token| parse_corner(token, data))
+        .collect::<Result<Vec<_>, _>>()?;
+    if corners.len() < 3 {
+        return Err(format!("face needs at least 3 vertices, found {}", corners.len()));
+    }
+
+    // Attributes are only kept when every corner of the polygon supplies them.
+    let has_uvs = corners.iter().all(|c| c.uv.is_some());
+    let has_normals = corners.iter().all(|c| c.normal.is_some());
+
+    let faces = (1..corners.len() - 1)
+        .map(|i| {
+            let tri = [&corners[0], &corners[i], &corners[i + 1]];
+            MeshFace {
+                vertices: tri.map(|c| c.vertex),
+                uvs: has_uvs.then(|| tri.map(|c| c.uv.unwrap())),
+                normals: has_normals.then(|| tri.map(|c| c.normal.unwrap())),
+                material,
+            }
+        })
+        .collect();
+    Ok(faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::export::SceneExporter;
+    use crate::scene::{MaterialDescription, TextureRef};
+
+    /// Writes `files` into a fresh directory and returns the path of the first one. The
+    /// directory is deleted when the returned guard is dropped.
+    fn write_files(files: &[(&str, &str)]) -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        for (name, contents) in files {
+            fs::write(dir.path().join(name), contents).unwrap();
+        }
+        let path = dir.path().join(files[0].0);
+        (dir, path)
+    }
+
+    fn options(smooth_normals: bool) -> ObjOptions {
+        ObjOptions {
+            smooth_normals,
+            default_material: Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        }
+    }
+
+    fn parse_error_line(result: Result<TriangleMesh, ObjError>) -> (usize, String) {
+        match result {
+            Err(ObjError::Parse { line, message, .. }) => (line, message),
+            Err(e) => panic!("expected a parse error, got {e}"),
+            Ok(_) => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn parse_errors_report_the_line_number() {
+        let (_dir, path) = write_files(&[("bad.obj", "# a comment\nv 0 0 0\n\nv 1 0\n")]);
+        let (line, message) = parse_error_line(load_obj(path, &options(false)));
+        assert_eq!(line, 4);
+        assert!(message.contains("vertex position"), "{message}");
+
+        let (_dir, path) = write_files(&[("bad.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n")]);
+        let (line, message) = parse_error_line(load_obj(path, &options(false)));
+        assert_eq!(line, 4);
+        assert!(message.contains("out of range"), "{message}");
+    }
+
+    #[test]
+    fn mtl_errors_report_the_mtl_file_and_line() {
+        let (_dir, path) = write_files(&[
+            ("scene.obj", "mtllib scene.mtl\n"),
+            ("scene.mtl", "newmtl red\nKd 1 0 0\nillum x\n"),
+        ]);
+        match load_obj(path, &options(false)) {
+            Err(ObjError::Parse { path, line, .. }) => {
+                assert_eq!(path.file_name().unwrap(), "scene.mtl");
+                assert_eq!(line, 3);
+            },
+            _ => panic!("expected a parse error in the MTL file"),
+        }
+    }
+
+    #[test]
+    fn polygons_are_fan_triangulated_with_relative_indices() {
+        let data = MeshData { positions: vec![Point3::zero(); 5], ..Default::default() };
+        let faces = parse_face("-4 -3 -2 -1".split_whitespace(), &data, 0).unwrap();
+        let vertices: Vec<_> = faces.iter().map(|f| f.vertices).collect();
+        assert_eq!(vertices, [[1, 2, 3], [1, 3, 4]]);
+        assert!(faces.iter().all(|f| f.normals.is_none() && f.uvs.is_none()));
+    }
+
+    fn metal_albedo(params: &MtlParams) -> [f64; 3] {
+        match params.to_material().describe(&mut SceneExporter::new()) {
+            Ok(MaterialDescription::Metal { albedo: TextureRef::Color(color), .. }) => color,
+            other => panic!("expected a metal, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reflective_materials_use_the_specular_color() {
+        let params = MtlParams {
+            diffuse: Color::new(0.1, 0.2, 0.3),
+            specular: Some(Color::new(0.9, 0.8, 0.7)),
+            illum: 3,
+            ..Default::default()
+        };
+        assert_eq!(metal_albedo(&params), [0.9, 0.8, 0.7]);
+    }
+
+    #[test]
+    fn reflective_materials_without_ks_fall_back_to_the_diffuse_color() {
+        let params = MtlParams { diffuse: Color::new(0.1, 0.2, 0.3), illum: 5, ..Default::default() };
+        assert_eq!(metal_albedo(&params), [0.1, 0.2, 0.3]);
+    }
+}
\ No newline at end of file
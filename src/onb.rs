@@ -0,0 +1,35 @@
+use vec3::cross;
+
+use crate::prelude::*;
+
+/// Orthonormal basis built around a given `w` axis.
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    pub fn new(n: Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let v = cross(w, a).unit_vector();
+        let u = cross(w, v);
+        Self { axis: [u, v, w] }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.axis[0]
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.axis[1]
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.axis[2]
+    }
+
+    /// Transform from basis coordinates to local space.
+    pub fn transform(&self, v: Vec3) -> Vec3 {
+        v.x * self.axis[0] + v.y * self.axis[1] + v.z * self.axis[2]
+    }
+}
\ No newline at end of file
@@ -0,0 +1,318 @@
+use std::{fmt, io::{self, Cursor, Write}, path::Path};
+
+use exr::prelude::{f16, Image as ExrImage, SpecificChannels, Vec2, WritableImage};
+use image::{
+    codecs::{bmp::BmpEncoder, hdr::HdrEncoder, png::PngEncoder, tga::TgaEncoder},
+    ExtendedColorType, ImageEncoder, Rgb,
+};
+
+use crate::prelude::*;
+use crate::color::{to_rgb16, to_rgb8};
+use crate::framebuffer::Framebuffer;
+
+/// File format used for the rendered image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Plain-text P3 PPM.
+    PpmAscii,
+    /// Binary P6 PPM.
+    PpmBinary,
+    Png8,
+    Png16,
+    Bmp,
+    Tga,
+    /// OpenEXR with 16-bit half float channels.
+    ExrHalf,
+    /// OpenEXR with 32-bit float channels.
+    ExrFloat,
+    /// Radiance RGBE.
+    Hdr,
+    /// Portable float map.
+    Pfm,
+}
+
+#[derive(Debug)]
+pub struct UnsupportedFormat(String);
+
+impl fmt::Display for UnsupportedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedFormat {}
+
+impl ImageFormat {
+    /// Picks the format from the output file's extension. See `from_extension`.
+    pub fn from_path(
+        path: &Path,
+        bit_depth: Option<u8>,
+        ascii: bool,
+    ) -> Result<Self, UnsupportedFormat> {
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .ok_or_else(|| UnsupportedFormat(format!(
+                "cannot tell the image format of '{}' without a file extension",
+                path.display(),
+            )))?;
+        Self::from_extension(extension, bit_depth, ascii)
+    }
+
+    /// Picks the format named by a file extension. `bit_depth` selects between 8- and 16-bit
+    /// PNG or 16- and 32-bit EXR, and `ascii` selects P3 over P6 for `ppm`.
+    pub fn from_extension(
+        extension: &str,
+        bit_depth: Option<u8>,
+        ascii: bool,
+    ) -> Result<Self, UnsupportedFormat> {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+
+        let format = match extension.as_str() {
+            "ppm" if ascii => ImageFormat::PpmAscii,
+            "ppm" => ImageFormat::PpmBinary,
+            "png" if bit_depth == Some(16) => ImageFormat::Png16,
+            "png" => ImageFormat::Png8,
+            "bmp" => ImageFormat::Bmp,
+            "tga" => ImageFormat::Tga,
+            "exr" if bit_depth == Some(32) => ImageFormat::ExrFloat,
+            "exr" => ImageFormat::ExrHalf,
+            "hdr" => ImageFormat::Hdr,
+            "pfm" => ImageFormat::Pfm,
+            _ => return Err(UnsupportedFormat(format!(
+                "unknown image extension '.{extension}' \
+                (expected one of: ppm, png, bmp, tga, exr, hdr, pfm)"
+            ))),
+        };
+
+        if ascii && format != ImageFormat::PpmAscii {
+            return Err(UnsupportedFormat("ASCII output is only available for .ppm".into()));
+        }
+        match (format, bit_depth) {
+            (_, None)
+            | (ImageFormat::PpmAscii | ImageFormat::PpmBinary, Some(8))
+            | (ImageFormat::Png8 | ImageFormat::Bmp | ImageFormat::Tga, Some(8))
+            | (ImageFormat::Png16 | ImageFormat::ExrHalf, Some(16))
+            | (ImageFormat::ExrFloat | ImageFormat::Hdr | ImageFormat::Pfm, Some(32)) => Ok(format),
+            (_, Some(depth)) => Err(UnsupportedFormat(format!(
+                "{depth}-bit output is not supported for .{extension}"
+            ))),
+        }
+    }
+
+    /// Encodes the image as this format. The framebuffer holds linear radiance; low dynamic
+    /// range formats gamma-correct and clamp it, while the HDR formats write it unchanged.
+    pub fn encode<W: Write>(&self, image: &Framebuffer, mut writer: W) -> io::Result<()> {
+        let writer = &mut writer;
+        let (width, height, pixels) = (image.width(), image.height(), image.pixels());
+        match self {
+            ImageFormat::PpmAscii => {
+                writeln!(writer, "P3\n{width} {height}\n255")?;
+                for pixel in pixels {
+                    write_color(writer, pixel)?;
+                }
+                Ok(())
+            },
+            ImageFormat::PpmBinary => {
+                writeln!(writer, "P6\n{width} {height}\n255")?;
+                writer.write_all(&rgb8_bytes(pixels))
+            },
+            ImageFormat::Png8 => PngEncoder::new(writer)
+                .write_image(&rgb8_bytes(pixels), width, height, ExtendedColorType::Rgb8)
+                .map_err(io::Error::other),
+            ImageFormat::Png16 => {
+                // The PNG encoder expects 16-bit samples in native byte order.
+                let bytes: Vec<u8> = pixels.iter()
+                    .flat_map(to_rgb16)
+                    .flat_map(u16::to_ne_bytes)
+                    .collect();
+                PngEncoder::new(writer)
+                    .write_image(&bytes, width, height, ExtendedColorType::Rgb16)
+                    .map_err(io::Error::other)
+            },
+            ImageFormat::Bmp => BmpEncoder::new(writer)
+                .encode(&rgb8_bytes(pixels), width, height, ExtendedColorType::Rgb8)
+                .map_err(io::Error::other),
+            ImageFormat::Tga => TgaEncoder::new(writer)
+                .encode(&rgb8_bytes(pixels), width, height, ExtendedColorType::Rgb8)
+                .map_err(io::Error::other),
+            ImageFormat::ExrHalf => write_exr(writer, width, height, pixels, f16::from_f64),
+            ImageFormat::ExrFloat => write_exr(writer, width, height, pixels, |c| c as f32),
+            ImageFormat::Hdr => {
+                let rgb: Vec<Rgb<f32>> = pixels.iter()
+                    .map(|p| Rgb([p.x as f32, p.y as f32, p.z as f32]))
+                    .collect();
+                HdrEncoder::new(writer)
+                    .encode(&rgb, width as usize, height as usize)
+                    .map_err(io::Error::other)
+            },
+            ImageFormat::Pfm => {
+                // A negative scale marks the samples as little endian. Rows run bottom to top.
+                writeln!(writer, "PF\n{width} {height}\n-1.0")?;
+                let bytes: Vec<u8> = pixels.chunks(width as usize)
+                    .rev()
+                    .flatten()
+                    .flat_map(|p| [p.x, p.y, p.z])
+                    .flat_map(|c| (c as f32).to_le_bytes())
+                    .collect();
+                writer.write_all(&bytes)
+            },
+        }
+    }
+}
+
+fn write_exr<W: Write, T>(
+    writer: &mut W,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+    convert: impl Fn(f64) -> T + Sync,
+) -> io::Result<()>
+where
+    T: exr::prelude::IntoSample,
+{
+    let channels = SpecificChannels::rgb(|Vec2(x, y): Vec2<usize>| {
+        let p = pixels[y * width as usize + x];
+        (convert(p.x), convert(p.y), convert(p.z))
+    });
+
+    // The EXR writer needs to seek, so encode into memory and copy the result out.
+    let mut buffer = Cursor::new(Vec::new());
+    ExrImage::from_channels((width as usize, height as usize), channels)
+        .write()
+        .to_buffered(&mut buffer)
+        .map_err(io::Error::other)?;
+    writer.write_all(buffer.get_ref())
+}
+
+fn rgb8_bytes(pixels: &[Color]) -> Vec<u8> {
+    pixels.iter().flat_map(to_rgb8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat as Codec, RgbImage};
+
+    /// Two rows of two pixels, including a component outside the displayable range.
+    fn sample_image() -> Framebuffer {
+        Framebuffer::from_pixels(2, 2, vec![
+            Color::new(1.0, 0.0, 0.25),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 4.0),
+            Color::new(0.25, 0.25, 0.25),
+        ])
+    }
+
+    /// `sample_image` gamma-corrected and clamped to bytes.
+    const RGB8: [u8; 12] = [255, 0, 128, 0, 255, 0, 0, 0, 255, 128, 128, 128];
+
+    fn encode(format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        format.encode(&sample_image(), &mut bytes).unwrap();
+        bytes
+    }
+
+    fn decode_rgb8(bytes: &[u8], codec: Codec) -> RgbImage {
+        let decoded = image::load_from_memory_with_format(bytes, codec).unwrap().to_rgb8();
+        assert_eq!(decoded.dimensions(), (2, 2));
+        decoded
+    }
+
+    #[test]
+    fn formats_follow_extension_and_bit_depth() {
+        let pick = |ext, depth, ascii| ImageFormat::from_extension(ext, depth, ascii);
+        assert_eq!(pick("PNG", None, false).unwrap(), ImageFormat::Png8);
+        assert_eq!(pick(".png", Some(16), false).unwrap(), ImageFormat::Png16);
+        assert_eq!(pick("exr", Some(32), false).unwrap(), ImageFormat::ExrFloat);
+        assert_eq!(pick("ppm", None, true).unwrap(), ImageFormat::PpmAscii);
+        assert!(pick("png", Some(32), false).is_err());
+        assert!(pick("png", None, true).is_err());
+        assert!(pick("jpg", None, false).is_err());
+        assert!(ImageFormat::from_path(Path::new("image"), None, false).is_err());
+    }
+
+    #[test]
+    fn ppm_ascii_writes_one_pixel_per_line() {
+        let text = String::from_utf8(encode(ImageFormat::PpmAscii)).unwrap();
+        assert_eq!(text, "P3\n2 2\n255\n255 0 128\n0 255 0\n0 0 255\n128 128 128\n");
+    }
+
+    #[test]
+    fn ppm_binary_writes_a_header_then_raw_bytes() {
+        let bytes = encode(ImageFormat::PpmBinary);
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], RGB8);
+    }
+
+    #[test]
+    fn png_bmp_and_tga_decode_to_the_same_bytes() {
+        for (format, codec, magic) in [
+            (ImageFormat::Png8, Codec::Png, &b"\x89PNG"[..]),
+            (ImageFormat::Bmp, Codec::Bmp, b"BM"),
+            (ImageFormat::Tga, Codec::Tga, b""),
+        ] {
+            let bytes = encode(format);
+            assert!(bytes.starts_with(magic), "{format:?}");
+            assert_eq!(decode_rgb8(&bytes, codec).into_raw(), RGB8, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn png16_keeps_sixteen_bits_per_sample() {
+        let decoded = image::load_from_memory_with_format(&encode(ImageFormat::Png16), Codec::Png)
+            .unwrap()
+            .to_rgb16();
+        let expected: Vec<u16> = sample_image().pixels().iter().flat_map(to_rgb16).collect();
+        assert_eq!(decoded.into_raw(), expected);
+        assert_eq!(expected[2], 32768);
+    }
+
+    #[test]
+    fn hdr_keeps_values_above_one() {
+        let bytes = encode(ImageFormat::Hdr);
+        assert!(bytes.starts_with(b"#?RADIANCE"));
+        let decoded = image::load_from_memory_with_format(&bytes, Codec::Hdr).unwrap().to_rgb32f();
+        let expected = [1.0, 0.0, 0.25, 0.0, 1.0, 0.0, 0.0, 0.0, 4.0, 0.25, 0.25, 0.25];
+        assert_eq!(decoded.into_raw(), expected);
+    }
+
+    #[test]
+    fn pfm_writes_little_endian_rows_bottom_up() {
+        let bytes = encode(ImageFormat::Pfm);
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+
+        let samples: Vec<f32> = bytes[header.len()..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        let bottom_row = [0.0, 0.0, 4.0, 0.25, 0.25, 0.25];
+        let top_row = [1.0, 0.0, 0.25, 0.0, 1.0, 0.0];
+        assert_eq!(samples, [bottom_row, top_row].concat());
+    }
+
+    #[test]
+    fn exr_round_trips_through_the_exr_reader() {
+        use exr::prelude::{read, ReadChannels, ReadLayers};
+
+        for format in [ImageFormat::ExrHalf, ImageFormat::ExrFloat] {
+            let bytes = encode(format);
+            assert!(bytes.starts_with(&[0x76, 0x2f, 0x31, 0x01]), "{format:?}");
+
+            let image = read()
+                .no_deep_data()
+                .largest_resolution_level()
+                .rgb_channels(
+                    |size, _| vec![[0.0_f32; 3]; size.width() * size.height()],
+                    |pixels, Vec2(x, y), (r, g, b): (f32, f32, f32)| pixels[y * 2 + x] = [r, g, b],
+                )
+                .first_valid_layer()
+                .all_attributes()
+                .from_buffered(Cursor::new(bytes))
+                .unwrap();
+            let pixels = image.layer_data.channel_data.pixels;
+            assert_eq!(pixels, [[1.0, 0.0, 0.25], [0.0, 1.0, 0.0], [0.0, 0.0, 4.0], [0.25; 3]]);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,114 @@
+use std::f64::consts::PI;
+
+use crate::prelude::*;
+use crate::onb::Onb;
+use crate::sampler::{sample_cosine_hemisphere, sample_henyey_greenstein, sample_uniform_sphere};
+
+/// A probability density over directions, used to importance sample scattered rays.
+pub trait Pdf {
+    /// The density of generating `direction`.
+    fn value(&self, direction: Vec3) -> f64;
+
+    /// Draws a direction from the distribution.
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3;
+}
+
+/// Uniform density over the unit sphere.
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        sample_uniform_sphere(sampler.get_2d())
+    }
+}
+
+/// Density proportional to the cosine of the angle with `w`, matching a Lambertian surface.
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(w: Vec3) -> Self {
+        Self { uvw: Onb::new(w) }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        let cosine_theta = dot(direction.unit_vector(), self.uvw.w());
+        f64::max(0.0, cosine_theta / PI)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        self.uvw.transform(sample_cosine_hemisphere(sampler.get_2d()))
+    }
+}
+
+/// The Henyey-Greenstein phase function: the density of light scattering by an angle with
+/// cosine `cos_theta` from its direction of travel. `g` is the average of that cosine, between -1
+/// for light bouncing straight back and 1 for light carrying straight on.
+pub fn henyey_greenstein(cos_theta: f64, g: f64) -> f64 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+}
+
+/// Directions scattered by the Henyey-Greenstein phase function from light travelling along
+/// `direction`.
+pub struct HenyeyGreensteinPdf {
+    uvw: Onb,
+    g: f64,
+}
+
+impl HenyeyGreensteinPdf {
+    pub fn new(direction: Vec3, g: f64) -> Self {
+        Self { uvw: Onb::new(direction), g }
+    }
+}
+
+impl Pdf for HenyeyGreensteinPdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        henyey_greenstein(dot(direction.unit_vector(), self.uvw.w()), self.g)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        self.uvw.transform(sample_henyey_greenstein(sampler.get_2d(), self.g))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::IndependentSampler;
+
+    /// Estimates the integral of `pdf` over the sphere of directions.
+    fn total(pdf: &dyn Pdf) -> f64 {
+        let mut sampler = IndependentSampler::new(3);
+        let n = 200_000;
+        let sum: f64 = (0..n).map(|_| pdf.value(sample_uniform_sphere(sampler.get_2d()))).sum();
+        4.0 * PI * sum / n as f64
+    }
+
+    #[test]
+    fn densities_integrate_to_one() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert!((total(&SpherePdf) - 1.0).abs() < 1e-9);
+        assert!((total(&CosinePdf::new(up)) - 1.0).abs() < 0.01);
+        assert!((total(&HenyeyGreensteinPdf::new(up, 0.6)) - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn cosine_directions_stay_in_the_hemisphere() {
+        let w = Vec3::new(1.0, 2.0, -1.0);
+        let pdf = CosinePdf::new(w);
+        let mut sampler = IndependentSampler::new(4);
+        for _ in 0..1000 {
+            let direction = pdf.generate(&mut sampler);
+            assert!(dot(direction, w) >= 0.0);
+            assert!(pdf.value(direction) >= 0.0);
+        }
+    }
+}
\ No newline at end of file
@@ -2,20 +2,27 @@ pub use crate::{ray, vec3};
 pub use crate::vec3::Color;
 pub use crate::interval::Interval;
 pub use crate::color::write_color;
-use rand::Rng;
 pub use vec3::{dot, Point3, Vec3};
 pub use ray::Ray;
 pub use crate::hittable::{HitRecord, Hittable};
 pub use crate::hittable_list::HittableList;
+pub use crate::aabb::Aabb;
 pub use crate::camera::Camera;
+pub use crate::background::Background;
 pub use crate::material::Material;
+pub use crate::sampler::Sampler;
 
 
 pub use std::rc::Rc;
-pub use std::f64::INFINITY;
 
-pub use rand::random;
+pub use crate::rng::Rng;
+pub use rand::Rng as _;
 
-pub fn random_range(min: f64, max: f64) -> f64 {
-    rand::thread_rng().gen_range(min..max)
+/// A random number in [0, 1).
+pub fn random(rng: &mut Rng) -> f64 {
+    rng.gen()
+}
+
+pub fn random_range(rng: &mut Rng, min: f64, max: f64) -> f64 {
+    rng.gen_range(min..max)
 }
\ No newline at end of file
@@ -0,0 +1,711 @@
+use std::sync::Arc;
+
+use crate::prelude::*;
+use crate::animation::{Animated, AnimatedTransform, Interpolation, Keyframe};
+use crate::bvh::{BvhNode, SplitMethod};
+use crate::camera::ShutterCurve;
+use crate::instance::Instance;
+use crate::lights::LightList;
+use crate::grid::DensityGrid;
+use crate::material::{Dielectric, DiffuseLight, HenyeyGreenstein, Lambertian, Metal};
+use crate::medium::{ConstantMedium, GridMedium};
+use crate::quad::{make_box, Quad};
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::texture::{CheckerTexture, NoiseKind, NoiseTexture, UvCheckerTexture};
+use crate::transform::{Transform, Transformed};
+
+/// A built-in scene that can be rendered by name.
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Builds the scene, drawing any random placement from `rng`.
+    pub build: fn(&mut Rng) -> Scene,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "random-spheres",
+        description: "Final scene of Ray Tracing in One Weekend",
+        build: random_spheres,
+    },
+    Preset {
+        name: "bouncing-spheres",
+        description: "Random sphere field with motion-blurred diffuse spheres",
+        build: bouncing_spheres,
+    },
+    Preset {
+        name: "checkered-spheres",
+        description: "Two large spheres sharing a 3D checker texture",
+        build: checkered_spheres,
+    },
+    Preset {
+        name: "perlin-spheres",
+        description: "Marble Perlin noise on a sphere and the ground",
+        build: perlin_spheres,
+    },
+    Preset {
+        name: "simple-lights",
+        description: "Perlin spheres lit only by a quad and a sphere light",
+        build: simple_lights,
+    },
+    Preset {
+        name: "cornell-box",
+        description: "Cornell box with two white blocks",
+        build: cornell_box,
+    },
+    Preset {
+        name: "cornell-smoke",
+        description: "Cornell box with blocks of dark and light smoke",
+        build: cornell_smoke,
+    },
+    Preset {
+        name: "final-next-week",
+        description: "Final scene of Ray Tracing: The Next Week",
+        build: final_next_week,
+    },
+    Preset {
+        name: "cloud",
+        description: "A procedural cloud and a glowing fireball, both voxel grid media, at dusk",
+        build: cloud,
+    },
+    Preset {
+        name: "propeller",
+        description: "A spinning propeller and a ball on a curved path, blurred by a soft shutter",
+        build: propeller,
+    },
+    Preset {
+        name: "forest",
+        description: "Ten thousand instanced trees sharing one trunk and one crown",
+        build: forest,
+    },
+];
+
+pub const DEFAULT_PRESET: &str = "bouncing-spheres";
+
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// The small spheres scattered over the ground in the first book's cover scene. Diffuse spheres
+/// bounce upwards over the shutter interval when `bouncing` is set.
+fn sphere_field(world: &mut HittableList, bouncing: bool, rng: &mut Rng) {
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat: f64 = random(rng);
+            let center = Point3::new(
+                 a as f64 + 0.9 * random(rng),
+                 0.2,
+                 b as f64 + 0.9 * random(rng),
+            );
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).len() > 0.9 {
+                if choose_mat < 0.8 {
+                    // diffuse
+                    let albedo = Color::random(rng) * Color::random(rng);
+                    let sphere_material =
+                        Arc::new(Lambertian::new(albedo));
+                    if bouncing {
+                        let center2 = center
+                            + Vec3::new(0.0, random_range(rng, 0.0, 0.5), 0.0);
+                        world.objects.push(Box::new(
+                            Sphere::moving(center, center2, 0.2, sphere_material)
+                        ));
+                    } else {
+                        world.objects.push(Box::new(
+                            Sphere::stationary(center, 0.2, sphere_material)
+                        ));
+                    }
+                } else if choose_mat < 0.95 {
+                    // metal
+                    let albedo = Color::random_range(rng, 0.5, 1.0);
+                    let fuzz = random_range(rng, 0.0, 0.5);
+                    let sphere_material = Arc::new(Metal::new(albedo, fuzz));
+                    world.objects.push(Box::new(
+                        Sphere::stationary(center, 0.2, sphere_material)
+                    ));
+                } else {
+                    // glass
+                    let sphere_material =
+                        Arc::new(Dielectric::new(1.5));
+                    world.objects.push(Box::new(
+                        Sphere::stationary(center, 0.2, sphere_material)
+                    ));
+                }
+            }
+        }
+    }
+
+    let mat1 = Arc::new(Dielectric::new(1.5));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, 1.0, 0.0), 1.0, mat1)
+    ));
+
+    let mat2 =
+        Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(-4.0, 1.0, 0.0), 1.0, mat2)
+    ));
+
+    let mat3 =
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.0));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(4.0, 1.0, 0.0), 1.0, mat3)
+    ));
+}
+
+fn cover_camera(defocus_angle: f64) -> Camera {
+    Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        20.0,
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::zero(),
+        Vec3::new(0.0, 1.0, 0.0),
+        defocus_angle,
+        10.0,
+    )
+}
+
+fn random_spheres(rng: &mut Rng) -> Scene {
+    let mut world = HittableList::new();
+
+    let ground_material =
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material)
+    ));
+    sphere_field(&mut world, false, rng);
+
+    Scene { camera: cover_camera(0.6), world, lights: LightList::new() }
+}
+
+fn bouncing_spheres(rng: &mut Rng) -> Scene {
+    let mut world = HittableList::new();
+
+    let ground_material =
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground_material)
+    ));
+    sphere_field(&mut world, true, rng);
+
+    Scene { camera: cover_camera(0.6), world, lights: LightList::new() }
+}
+
+fn checkered_spheres(_rng: &mut Rng) -> Scene {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.32,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    let checker_material = Arc::new(Lambertian::from_texture(checker));
+
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, -10.0, 0.0), 10.0, checker_material.clone())
+    ));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, 10.0, 0.0), 10.0, checker_material)
+    ));
+
+    Scene { camera: cover_camera(0.0), world, lights: LightList::new() }
+}
+
+fn perlin_world(rng: &mut Rng) -> HittableList {
+    let mut world = HittableList::new();
+
+    let pertext = Arc::new(NoiseTexture::new(4.0, NoiseKind::Marble, rng.gen()));
+    let noise_material = Arc::new(Lambertian::from_texture(pertext));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, -1000.0, 0.0), 1000.0, noise_material.clone())
+    ));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, 2.0, 0.0), 2.0, noise_material)
+    ));
+
+    world
+}
+
+fn perlin_spheres(rng: &mut Rng) -> Scene {
+    Scene { camera: cover_camera(0.0), world: perlin_world(rng), lights: LightList::new() }
+}
+
+fn simple_lights(rng: &mut Rng) -> Scene {
+    let mut world = perlin_world(rng);
+
+    let mut lights = LightList::new();
+    let light_color = Color::new(4.0, 4.0, 4.0);
+    let difflight: Arc<dyn Material> = Arc::new(DiffuseLight::new(light_color));
+    let sphere_light = Sphere::stationary(Point3::new(0.0, 7.0, 0.0), 2.0, difflight.clone());
+    let quad_light = Quad::new(
+        Point3::new(3.0, 1.0, -2.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 2.0, 0.0),
+        difflight,
+    );
+    add_light(&mut world, &mut lights, sphere_light, light_color);
+    add_light(&mut world, &mut lights, quad_light, light_color);
+
+    let mut camera = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        20.0,
+        Point3::new(26.0, 3.0, 6.0),
+        Point3::new(0.0, 2.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+    );
+    camera.background = Background::Solid(Color::zero());
+
+    Scene { camera, world, lights }
+}
+
+/// Adds an emitter to the world and to the lights sampled for direct lighting, sharing the one
+/// object between them.
+fn add_light(
+    world: &mut HittableList,
+    lights: &mut LightList,
+    light: impl Hittable + 'static,
+    radiance: Color,
+) {
+    let light: Arc<dyn Hittable> = Arc::new(light);
+    world.objects.push(Box::new(light.clone()));
+    lights.push(light, radiance);
+}
+
+/// The walls and ceiling light of the Cornell box. The light is also added to `lights`.
+fn cornell_walls(world: &mut HittableList, lights: &mut LightList) {
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light_color = Color::new(15.0, 15.0, 15.0);
+    let light = Arc::new(DiffuseLight::new(light_color));
+
+    let walls: [(Point3, Vec3, Vec3, Arc<dyn Material>); 5] = [
+        (Point3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), green),
+        (Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), red),
+        (Point3::new(0.0, 0.0, 0.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 555.0), white.clone()),
+        (Point3::new(555.0, 555.0, 555.0), Vec3::new(-555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -555.0), white.clone()),
+        (Point3::new(0.0, 0.0, 555.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), white),
+    ];
+    for (q, u, v, mat) in walls {
+        world.objects.push(Box::new(Quad::new(q, u, v, mat)));
+    }
+
+    let ceiling_light = Quad::new(
+        Point3::new(343.0, 554.0, 332.0),
+        Vec3::new(-130.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -105.0),
+        light,
+    );
+    add_light(world, lights, ceiling_light, light_color);
+}
+
+fn cornell_camera() -> Camera {
+    let mut camera = Camera::new(
+        1.0,
+        600,
+        200,
+        50,
+        40.0,
+        Point3::new(278.0, 278.0, -800.0),
+        Point3::new(278.0, 278.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+    );
+    camera.background = Background::Solid(Color::zero());
+    camera
+}
+
+fn cornell_box(_rng: &mut Rng) -> Scene {
+    let mut world = HittableList::new();
+    let mut lights = LightList::new();
+    cornell_walls(&mut world, &mut lights);
+
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    world.objects.push(Box::new(Transformed::new(
+        make_box(Point3::zero(), Point3::new(165.0, 330.0, 165.0), white.clone()),
+        Transform::rotate_y(15.0).then(Transform::translate(Vec3::new(265.0, 0.0, 295.0))),
+    )));
+    world.objects.push(Box::new(Transformed::new(
+        make_box(Point3::zero(), Point3::new(165.0, 165.0, 165.0), white),
+        Transform::rotate_y(-18.0).then(Transform::translate(Vec3::new(130.0, 0.0, 65.0))),
+    )));
+
+    Scene { camera: cornell_camera(), world, lights }
+}
+
+/// The Cornell box with its blocks replaced by smoke, one black and one white.
+fn cornell_smoke(_rng: &mut Rng) -> Scene {
+    let mut world = HittableList::new();
+    let mut lights = LightList::new();
+    cornell_walls(&mut world, &mut lights);
+
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let box1 = Transformed::new(
+        make_box(Point3::zero(), Point3::new(165.0, 330.0, 165.0), white.clone()),
+        Transform::rotate_y(15.0).then(Transform::translate(Vec3::new(265.0, 0.0, 295.0))),
+    );
+    let box2 = Transformed::new(
+        make_box(Point3::zero(), Point3::new(165.0, 165.0, 165.0), white),
+        Transform::rotate_y(-18.0).then(Transform::translate(Vec3::new(130.0, 0.0, 65.0))),
+    );
+    world.objects.push(Box::new(ConstantMedium::isotropic(box1, 0.01, Color::zero())));
+    world.objects.push(Box::new(ConstantMedium::isotropic(box2, 0.01, Color::new(1.0, 1.0, 1.0))));
+
+    Scene { camera: cornell_camera(), world, lights }
+}
+
+/// The second book's closing scene. There is no Earth image among the repository assets, so a
+/// UV checker globe stands in for the textured Earth.
+fn final_next_week(rng: &mut Rng) -> Scene {
+    let mut boxes1 = HittableList::new();
+    let ground = Arc::new(Lambertian::new(Color::new(0.48, 0.83, 0.53)));
+
+    let boxes_per_side = 20;
+    for i in 0..boxes_per_side {
+        for j in 0..boxes_per_side {
+            let w = 100.0;
+            let x0 = -1000.0 + i as f64 * w;
+            let z0 = -1000.0 + j as f64 * w;
+            let y0 = 0.0;
+            let x1 = x0 + w;
+            let y1 = random_range(rng, 1.0, 101.0);
+            let z1 = z0 + w;
+
+            boxes1.objects.push(Box::new(make_box(
+                Point3::new(x0, y0, z0),
+                Point3::new(x1, y1, z1),
+                ground.clone(),
+            )));
+        }
+    }
+
+    let mut world = HittableList::new();
+    world.objects.push(Box::new(BvhNode::new(boxes1, SplitMethod::Sah)));
+
+    let mut lights = LightList::new();
+    let light_color = Color::new(7.0, 7.0, 7.0);
+    let light: Arc<dyn Material> = Arc::new(DiffuseLight::new(light_color));
+    let ceiling_light = Quad::new(
+        Point3::new(123.0, 554.0, 147.0),
+        Vec3::new(300.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 265.0),
+        light,
+    );
+    add_light(&mut world, &mut lights, ceiling_light, light_color);
+
+    let center1 = Point3::new(400.0, 400.0, 200.0);
+    let center2 = center1 + Vec3::new(30.0, 0.0, 0.0);
+    let sphere_material = Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.1)));
+    world.objects.push(Box::new(Sphere::moving(center1, center2, 50.0, sphere_material)));
+
+    world.objects.push(Box::new(Sphere::stationary(
+        Point3::new(260.0, 150.0, 45.0),
+        50.0,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.objects.push(Box::new(Sphere::stationary(
+        Point3::new(0.0, 150.0, 145.0),
+        50.0,
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.9), 1.0)),
+    )));
+
+    // A glass ball filled with blue smoke, and a thin mist over the whole scene.
+    let boundary = || Sphere::stationary(
+        Point3::new(360.0, 150.0, 145.0),
+        70.0,
+        Arc::new(Dielectric::new(1.5)),
+    );
+    world.objects.push(Box::new(boundary()));
+    world.objects.push(Box::new(ConstantMedium::isotropic(
+        boundary(),
+        0.2,
+        Color::new(0.2, 0.4, 0.9),
+    )));
+    world.objects.push(Box::new(ConstantMedium::isotropic(
+        Sphere::stationary(Point3::zero(), 5000.0, Arc::new(Dielectric::new(1.5))),
+        0.0001,
+        Color::new(1.0, 1.0, 1.0),
+    )));
+
+    let globe = Arc::new(UvCheckerTexture::from_colors(
+        16.0,
+        8.0,
+        Color::new(0.1, 0.2, 0.5),
+        Color::new(0.2, 0.5, 0.2),
+    ));
+    world.objects.push(Box::new(Sphere::stationary(
+        Point3::new(400.0, 200.0, 400.0),
+        100.0,
+        Arc::new(Lambertian::from_texture(globe)),
+    )));
+    let pertext = Arc::new(NoiseTexture::new(0.2, NoiseKind::Marble, rng.gen()));
+    world.objects.push(Box::new(Sphere::stationary(
+        Point3::new(220.0, 280.0, 300.0),
+        80.0,
+        Arc::new(Lambertian::from_texture(pertext)),
+    )));
+
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let mut boxes2 = HittableList::new();
+    for _ in 0..1000 {
+        boxes2.objects.push(Box::new(Sphere::stationary(
+            Color::random_range(rng, 0.0, 165.0),
+            10.0,
+            white.clone(),
+        )));
+    }
+    world.objects.push(Box::new(Transformed::new(
+        BvhNode::new(boxes2, SplitMethod::Sah),
+        Transform::rotate_y(15.0).then(Transform::translate(Vec3::new(-100.0, 270.0, 395.0))),
+    )));
+
+    let mut camera = Camera::new(
+        1.0,
+        400,
+        250,
+        4,
+        40.0,
+        Point3::new(478.0, 278.0, -600.0),
+        Point3::new(278.0, 278.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+    );
+    camera.background = Background::Solid(Color::zero());
+
+    Scene { camera, world, lights }
+}
+
+/// Two heterogeneous media over a ground plane: a forward scattering cloud lit by a low sun,
+/// and a small fireball below it whose glow follows its density.
+fn cloud(_rng: &mut Rng) -> Scene {
+    let mut world = HittableList::new();
+    let mut lights = LightList::new();
+
+    let ground = Arc::new(Lambertian::new(Color::new(0.4, 0.4, 0.35)));
+    world.objects.push(Box::new(
+        Sphere::stationary(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground)
+    ));
+
+    let sun_color = Color::new(60.0, 50.0, 40.0);
+    let sun_material: Arc<dyn Material> = Arc::new(DiffuseLight::new(sun_color));
+    let sun = Sphere::stationary(Point3::new(-25.0, 20.0, 10.0), 4.0, sun_material);
+    add_light(&mut world, &mut lights, sun, sun_color);
+
+    // The boundaries only shape the media and are never drawn, so their material doesn't matter.
+    let unseen: Arc<dyn Material> = Arc::new(Lambertian::new(Color::zero()));
+    let cloud = GridMedium::new(
+        make_box(Point3::new(-3.0, 1.5, -2.0), Point3::new(3.0, 4.5, 2.0), unseen.clone()),
+        Arc::new(DensityGrid::noise([96, 48, 64], 2.0, 7)),
+        3.0,
+        Arc::new(HenyeyGreenstein::new(Color::new(0.95, 0.95, 0.95), 0.3)),
+    );
+    world.objects.push(Box::new(cloud));
+
+    let fireball = Arc::new(DensityGrid::noise([32, 32, 32], 3.0, 11));
+    let fire = GridMedium::new(
+        Sphere::stationary(Point3::new(1.5, 0.8, 2.5), 0.8, unseen),
+        fireball.clone(),
+        4.0,
+        Arc::new(HenyeyGreenstein::new(Color::new(0.3, 0.3, 0.3), 0.0)),
+    )
+    .with_emission(fireball, Color::new(4.0, 1.2, 0.2));
+    world.objects.push(Box::new(fire));
+
+    let mut camera = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        35.0,
+        Point3::new(4.0, 2.5, 12.0),
+        Point3::new(0.0, 2.3, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+    );
+    camera.background = Background::Gradient {
+        bottom: Color::new(0.5, 0.35, 0.3),
+        top: Color::new(0.1, 0.15, 0.35),
+    };
+
+    Scene { camera, world, lights }
+}
+
+/// Keyframed motion blur: a three-bladed propeller turns an eighth of a revolution while the
+/// shutter is open, and a ball follows a spline through three points. The shutter opens and
+/// closes gradually, which softens the ends of the streaks.
+fn propeller(_rng: &mut Rng) -> Scene {
+    let mut world = HittableList::new();
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.objects.push(Box::new(Sphere::stationary(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    let metal = Arc::new(Metal::new(Color::new(0.8, 0.8, 0.85), 0.2));
+    let paint: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.8, 0.2, 0.1)));
+    let mut rotor = HittableList::new();
+    rotor.objects.push(Box::new(Sphere::stationary(Point3::zero(), 0.2, metal)));
+    for blade in 0..3 {
+        rotor.objects.push(Box::new(Transformed::new(
+            make_box(Point3::new(0.1, -0.08, -0.02), Point3::new(1.2, 0.08, 0.02), paint.clone()),
+            Transform::rotate_x(20.0).then(Transform::rotate_z(120.0 * blade as f64)),
+        )));
+    }
+    let spin = AnimatedTransform::new(
+        vec![
+            Keyframe::at(0.0).translate(Vec3::new(-0.8, 1.5, 0.0)),
+            Keyframe::at(1.0).translate(Vec3::new(-0.8, 1.5, 0.0)).rotate(Vec3::new(0.0, 0.0, -45.0)),
+        ],
+        Interpolation::Linear,
+    );
+    world.objects.push(Box::new(Animated::new(BvhNode::new(rotor, SplitMethod::Sah), spin)));
+
+    let blue = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.8)));
+    let arc = AnimatedTransform::new(
+        vec![
+            Keyframe::at(0.0).translate(Vec3::new(1.0, 0.3, 0.5)),
+            Keyframe::at(0.5).translate(Vec3::new(1.6, 1.0, 0.5)),
+            Keyframe::at(1.0).translate(Vec3::new(2.2, 0.3, 0.5)),
+        ],
+        Interpolation::Spline,
+    );
+    world.objects.push(Box::new(Animated::new(Sphere::stationary(Point3::zero(), 0.3, blue), arc)));
+
+    let mut camera = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        40.0,
+        Point3::new(0.5, 1.2, 5.0),
+        Point3::new(0.5, 1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+    );
+    camera.shutter_curve =
+        ShutterCurve::new(vec![(0.0, 0.0), (0.25, 1.0), (0.75, 1.0), (1.0, 0.0)]).unwrap();
+
+    Scene { camera, world, lights: LightList::new() }
+}
+
+/// A grid of trees, each placed as two instances: a shared box trunk and a shared crown of
+/// three spheres, turned and scaled at random. Crowns take one of a few foliage colors through
+/// a material override.
+fn forest(rng: &mut Rng) -> Scene {
+    let bark = Arc::new(Lambertian::new(Color::new(0.3, 0.2, 0.1)));
+    let trunk: Arc<dyn Hittable> = Arc::new(make_box(
+        Point3::new(-0.08, 0.0, -0.08),
+        Point3::new(0.08, 0.9, 0.08),
+        bark,
+    ));
+
+    let leaves: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.2, 0.5, 0.15)));
+    let mut crown = HittableList::new();
+    for (center, radius) in [
+        (Point3::new(0.0, 1.2, 0.0), 0.45),
+        (Point3::new(0.25, 0.95, 0.1), 0.3),
+        (Point3::new(-0.2, 1.0, -0.15), 0.32),
+    ] {
+        crown.objects.push(Box::new(Sphere::stationary(center, radius, leaves.clone())));
+    }
+    let crown: Arc<dyn Hittable> = Arc::new(BvhNode::new(crown, SplitMethod::Sah));
+
+    let foliage: Vec<Arc<dyn Material>> = [
+        Color::new(0.15, 0.45, 0.1),
+        Color::new(0.35, 0.5, 0.1),
+        Color::new(0.7, 0.4, 0.05),
+        Color::new(0.6, 0.15, 0.05),
+    ]
+    .into_iter()
+    .map(|color| Arc::new(Lambertian::new(color)) as Arc<dyn Material>)
+    .collect();
+
+    let mut world = HittableList::new();
+    let ground = Arc::new(Lambertian::new(Color::new(0.35, 0.3, 0.2)));
+    world.objects.push(Box::new(Quad::new(
+        Point3::new(-200.0, 0.0, -200.0),
+        Vec3::new(400.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 400.0),
+        ground,
+    )));
+
+    for i in -50..50 {
+        for j in -50..50 {
+            let position = Vec3::new(
+                i as f64 + random_range(rng, 0.1, 0.9),
+                0.0,
+                j as f64 + random_range(rng, 0.1, 0.9),
+            );
+            let size = random_range(rng, 0.7, 1.4);
+            let transform = Transform::scale(Vec3::new(size, size, size))
+                .then(Transform::rotate_y(random_range(rng, 0.0, 360.0)))
+                .then(Transform::translate(position));
+
+            let leaves = foliage[rng.gen_range(0..foliage.len())].clone();
+            world.objects.push(Box::new(Instance::new(trunk.clone(), transform)));
+            world.objects.push(Box::new(Instance::new(crown.clone(), transform).with_material(leaves)));
+        }
+    }
+
+    let camera = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        35.0,
+        Point3::new(6.0, 2.5, -56.0),
+        Point3::new(0.0, 1.0, -40.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.0,
+        10.0,
+    );
+
+    Scene { camera, world, lights: LightList::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::rng;
+
+    #[test]
+    fn names_are_unique_and_include_the_default() {
+        let names: HashSet<_> = PRESETS.iter().map(|preset| preset.name).collect();
+        assert_eq!(names.len(), PRESETS.len());
+        assert!(find(DEFAULT_PRESET).is_some());
+        assert!(find("no-such-scene").is_none());
+    }
+
+    #[test]
+    fn every_preset_builds_the_same_scene_from_the_same_seed() {
+        for preset in PRESETS {
+            let a = (preset.build)(&mut rng::seeded(5));
+            let b = (preset.build)(&mut rng::seeded(5));
+            let (box_a, box_b) = (a.world.bounding_box(), b.world.bounding_box());
+            assert!(!a.world.objects.is_empty(), "{} is empty", preset.name);
+            assert!(!box_a.is_empty(), "{} has no extent", preset.name);
+            assert_eq!(a.world.objects.len(), b.world.objects.len(), "{}", preset.name);
+            assert_eq!((box_a.x.min, box_a.y.max, box_a.z.min), (box_b.x.min, box_b.y.max, box_b.z.min));
+        }
+    }
+}
\ No newline at end of file
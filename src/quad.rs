@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use vec3::cross;
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::scene::ObjectDescription;
+
+/// Parallelogram spanned by the edge vectors `u` and `v` from the corner `q`.
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+    normal: Vec3,
+    d: f64,
+    area: f64,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
+        let n = cross(u, v);
+        let normal = n.unit_vector();
+        let d = dot(normal, q);
+        let w = n / dot(n, n);
+        let area = n.len();
+
+        // Compute the bounding box of all four vertices.
+        let bbox_diagonal1 = Aabb::from_points(q, q + u + v);
+        let bbox_diagonal2 = Aabb::from_points(q + u, q + v);
+        let bbox = Aabb::from_boxes(&bbox_diagonal1, &bbox_diagonal2);
+
+        Self { q, u, v, w, mat, bbox, normal, d, area }
+    }
+
+    /// Given the hit point in plane coordinates, return None if it is outside the primitive,
+    /// otherwise the hit point's UV coordinates.
+    fn is_interior(a: f64, b: f64) -> Option<(f64, f64)> {
+        let unit_interval = Interval::new(0.0, 1.0);
+        if unit_interval.contains(a) && unit_interval.contains(b) {
+            Some((a, b))
+        } else {
+            None
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let denom = dot(self.normal, r.direction);
+
+        // No hit if the ray is parallel to the plane.
+        if denom.abs() < 1e-8 { return None; }
+
+        // Return None if the hit point parameter t is outside the ray interval.
+        let t = (self.d - dot(self.normal, r.origin)) / denom;
+        if !ray_t.surrounds(t) { return None; }
+
+        // Determine if the hit point lies within the planar shape using its plane coordinates.
+        let intersection = r.at(t);
+        let planar_hitpt_vector = intersection - self.q;
+        let alpha = dot(self.w, cross(planar_hitpt_vector, self.v));
+        let beta = dot(self.w, cross(self.u, planar_hitpt_vector));
+        let (u, v) = Self::is_interior(alpha, beta)?;
+
+        let mut rec = HitRecord {
+            p: intersection,
+            normal: Default::default(),
+            mat: self.mat.clone(),
+            t,
+            u,
+            v,
+            front_face: Default::default(),
+        };
+        rec.set_face_normal(r, self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        self.area
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let Some(rec) = self.hit(&Ray::new(origin, direction, 0.0), Interval::new(0.001, f64::INFINITY))
+        else {
+            return 0.0;
+        };
+
+        // Convert the uniform density over the area to a density over solid angle.
+        let distance_squared = rec.t * rec.t * direction.len_squared();
+        let cosine = (dot(direction, rec.normal) / direction.len()).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (a, b) = sampler.get_2d();
+        let p = self.q + (a * self.u) + (b * self.v);
+        p - origin
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let material = scene.material(&self.mat)?;
+        scene.push_object(ObjectDescription::Quad {
+            q: self.q.into(),
+            u: self.u.into(),
+            v: self.v.into(),
+            material,
+        });
+        Ok(())
+    }
+}
+
+/// Returns the 3D box (six sides) that contains the two opposite vertices a & b.
+pub fn make_box(a: Point3, b: Point3, mat: Arc<dyn Material>) -> HittableList {
+    let mut sides = HittableList::new();
+
+    // Construct the two opposite vertices with the minimum and maximum coordinates.
+    let min = Point3::new(f64::min(a.x, b.x), f64::min(a.y, b.y), f64::min(a.z, b.z));
+    let max = Point3::new(f64::max(a.x, b.x), f64::max(a.y, b.y), f64::max(a.z, b.z));
+
+    let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+    let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+    let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+    let faces = [
+        (Point3::new(min.x, min.y, max.z), dx, dy),  // front
+        (Point3::new(max.x, min.y, max.z), -dz, dy), // right
+        (Point3::new(max.x, min.y, min.z), -dx, dy), // back
+        (Point3::new(min.x, min.y, min.z), dz, dy),  // left
+        (Point3::new(min.x, max.y, max.z), dx, -dz), // top
+        (Point3::new(min.x, min.y, min.z), dx, dz),  // bottom
+    ];
+    for (q, u, v) in faces {
+        sides.objects.push(Box::new(Quad::new(q, u, v, mat.clone())));
+    }
+
+    sides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn unit_quad() -> Quad {
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Quad::new(Point3::zero(), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), mat)
+    }
+
+    #[test]
+    fn hit_reports_plane_coordinates_and_faces_the_ray() {
+        let r = Ray::new(Point3::new(0.25, 0.75, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = unit_quad().hit(&r, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-12);
+        assert!((rec.u - 0.25).abs() < 1e-12 && (rec.v - 0.75).abs() < 1e-12);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn misses_outside_the_edges() {
+        let r = Ray::new(Point3::new(1.5, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(unit_quad().hit(&r, Interval::new(0.001, f64::INFINITY)).is_none());
+    }
+
+    #[test]
+    fn hits_exactly_at_the_interval_bounds_are_rejected() {
+        let r = Ray::new(Point3::new(0.5, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(unit_quad().hit(&r, Interval::new(0.0, 2.0)).is_none());
+        assert!(unit_quad().hit(&r, Interval::new(2.0, 3.0)).is_none());
+    }
+
+    #[test]
+    fn box_has_six_outward_facing_sides() {
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let sides = make_box(Point3::new(1.0, 1.0, 1.0), Point3::new(-1.0, -1.0, -1.0), mat);
+        assert_eq!(sides.objects.len(), 6);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = sides.hit(&r, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-12);
+        assert!(rec.front_face);
+    }
+}
\ No newline at end of file
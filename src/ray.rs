@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
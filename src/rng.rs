@@ -0,0 +1,58 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg64Mcg;
+
+use crate::ray::Ray;
+
+/// The random number generator behind every random decision in a render. It is explicitly
+/// seeded everywhere, so the same seed always produces the same image.
+pub type Rng = Pcg64Mcg;
+
+pub fn seeded(seed: u64) -> Rng {
+    Rng::seed_from_u64(seed)
+}
+
+/// A generator seeded by the ray itself, for random decisions made while testing a ray for hits,
+/// where no sampler is at hand. Different samples trace different rays, so the streams are
+/// independent, and a render stays reproducible.
+pub fn for_ray(r: &Ray) -> Rng {
+    let (o, d) = (r.origin, r.direction);
+    seeded(hash(&[
+        o.x.to_bits(), o.y.to_bits(), o.z.to_bits(),
+        d.x.to_bits(), d.y.to_bits(), d.z.to_bits(),
+        r.time.to_bits(),
+    ]))
+}
+
+/// Hashes a list of values into one well-mixed 64-bit value, for seeding per-pixel and
+/// per-sample randomness so that the result doesn't depend on which thread renders what.
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e3779b97f4a7c15, |h, &v| mix(h ^ mix(v)))
+}
+
+/// The SplitMix64 finalizer, which scatters nearby inputs across the whole 64-bit range.
+fn mix(z: u64) -> u64 {
+    let z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng as _;
+
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_the_same_stream() {
+        let draw = |seed| seeded(seed).gen::<[u64; 4]>();
+        assert_eq!(draw(42), draw(42));
+        assert_ne!(draw(42), draw(43));
+    }
+
+    #[test]
+    fn hash_depends_on_every_value_and_their_order() {
+        assert_ne!(hash(&[1, 2]), hash(&[2, 1]));
+        assert_ne!(hash(&[1, 2]), hash(&[1, 2, 0]));
+        assert_eq!(hash(&[7, 8, 9]), hash(&[7, 8, 9]));
+    }
+}
\ No newline at end of file
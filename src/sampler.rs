@@ -0,0 +1,515 @@
+use std::f64::consts::PI;
+
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::rng;
+
+/// Supplies the random numbers of one pixel sample, one dimension at a time. The camera asks for
+/// the pixel offset, lens position and time first, then each bounce draws what it needs for
+/// light selection, light and BSDF directions, collisions in media and Russian roulette. Samplers that know which
+/// sample and dimension a value is for can spread the values of a pixel more evenly than
+/// independent random numbers do.
+pub trait Sampler {
+    /// Starts sample `index` of pixel `x`, `y`, numbering dimensions from zero again.
+    fn start_sample(&mut self, x: u32, y: u32, index: u32);
+
+    /// A value in [0, 1) for the next dimension.
+    fn get_1d(&mut self) -> f64;
+
+    /// A point in [0, 1)² for the next two dimensions.
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// The available samplers, for configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplerKind {
+    /// Independent uniform random numbers.
+    Independent,
+    /// Jittered strata, shuffled separately for every dimension.
+    Stratified,
+    /// The Halton sequence, randomly shifted per pixel.
+    Halton,
+    /// Owen-scrambled Sobol points, padded from two-dimensional sets.
+    #[default]
+    Sobol,
+    /// A rank-1 lattice shifted per pixel by a blue noise pattern.
+    BlueNoise,
+}
+
+impl SamplerKind {
+    /// Creates a sampler for `samples_per_pixel` samples in every pixel.
+    pub fn create(self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new(seed)),
+        }
+    }
+}
+
+/// Where a sampler is within the sample it is generating.
+#[derive(Default)]
+struct SampleState {
+    x: u32,
+    y: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl SampleState {
+    fn start(&mut self, x: u32, y: u32, index: u32) {
+        *self = Self { x, y, index, dimension: 0 };
+    }
+
+    /// Claims the next `count` dimensions and returns the first.
+    fn next(&mut self, count: u32) -> u32 {
+        let dimension = self.dimension;
+        self.dimension += count;
+        dimension
+    }
+
+    /// A hash of the pixel and `dimension`, for scrambling values of that dimension.
+    fn hash(&self, seed: u64, dimension: u32) -> u64 {
+        rng::hash(&[seed, self.x as u64, self.y as u64, dimension as u64])
+    }
+}
+
+fn to_unit(bits: u32) -> f64 {
+    bits as f64 / (1u64 << 32) as f64
+}
+
+/// Independent uniform random numbers, seeded per sample so that samples don't depend on each
+/// other.
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: rng::seeded(seed) }
+    }
+
+    /// A sampler seeded by the ray itself, for objects asked to `hit` a ray outside a render,
+    /// where no sampler is at hand.
+    pub fn for_ray(r: &Ray) -> Self {
+        Self { seed: 0, rng: rng::for_ray(r) }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.rng = Rng::seed_from_u64(rng::hash(&[self.seed, x as u64, y as u64, index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        random(&mut self.rng)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (random(&mut self.rng), random(&mut self.rng))
+    }
+}
+
+/// Splits every dimension into as many strata as there are samples, or every pair of
+/// dimensions into a square grid, and jitters one sample into each stratum. Each dimension
+/// visits its strata in its own random order, so the dimensions stay uncorrelated.
+pub struct StratifiedSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    state: SampleState,
+    rng: Rng,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        Self { seed, samples_per_pixel, state: SampleState::default(), rng: rng::seeded(seed) }
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+        self.rng = Rng::seed_from_u64(rng::hash(&[self.seed, x as u64, y as u64, index as u64]));
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next(1);
+        let hash = self.state.hash(self.seed, dimension);
+        let stratum = permutation_element(self.state.index, self.samples_per_pixel, hash as u32);
+        (stratum as f64 + random(&mut self.rng)) / self.samples_per_pixel as f64
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.state.next(2);
+        let n = (self.samples_per_pixel as f64).sqrt() as u32;
+
+        // Samples beyond the largest square grid that fits are left unstratified.
+        if self.state.index >= n * n {
+            return (random(&mut self.rng), random(&mut self.rng));
+        }
+
+        let hash = self.state.hash(self.seed, dimension);
+        let stratum = permutation_element(self.state.index, n * n, hash as u32);
+        (
+            ((stratum % n) as f64 + random(&mut self.rng)) / n as f64,
+            ((stratum / n) as f64 + random(&mut self.rng)) / n as f64,
+        )
+    }
+}
+
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89,
+    97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191,
+    193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283,
+    293, 307, 311,
+];
+
+/// The Halton sequence, which uses the radical inverse in the n-th prime base for the n-th
+/// dimension. The digits are Owen scrambled: each is replaced through a random permutation
+/// chosen by the pixel, the dimension and the digits before it, which breaks up the
+/// correlation between dimensions with large bases. Dimensions past the last tabulated prime
+/// are independent random numbers.
+pub struct HaltonSampler {
+    seed: u64,
+    state: SampleState,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: SampleState::default() }
+    }
+
+    fn sample(&self, dimension: u32) -> f64 {
+        let hash = self.state.hash(self.seed, dimension);
+        match PRIMES.get(dimension as usize) {
+            Some(&base) => owen_scrambled_radical_inverse(base, self.state.index, hash),
+            None => to_unit(rng::hash(&[hash, self.state.index as u64]) as u32),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next(1);
+        self.sample(dimension)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.state.next(2);
+        (self.sample(dimension), self.sample(dimension + 1))
+    }
+}
+
+/// Mirrors the base `base` digits of `index` around the radix point, replacing each digit
+/// through a permutation chosen by `hash`, its position and the digits before it. Past the
+/// last nonzero digit the permuted zeros are just uniform random digits, so they are filled in
+/// all at once.
+fn owen_scrambled_radical_inverse(base: u32, mut index: u32, hash: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_m = 1.0;
+    let mut reversed: u64 = 0;
+    let mut position = 0;
+    while index > 0 {
+        let digit = index % base;
+        index /= base;
+        let digit_hash = rng::hash(&[hash, position, reversed]);
+        let digit = permutation_element(digit, base, digit_hash as u32);
+        reversed = reversed * base as u64 + digit as u64;
+        inv_base_m *= inv_base;
+        position += 1;
+    }
+    let tail = to_unit(rng::hash(&[hash, position, reversed]) as u32);
+    ((reversed as f64 + tail) * inv_base_m).min(1.0 - f64::EPSILON / 2.0)
+}
+
+/// The first two dimensions of the Sobol sequence, which form a (0, 2)-sequence: every
+/// power-of-two prefix is stratified over every grid of that many cells. Each pair of
+/// dimensions gets its own Owen scrambling and its own shuffle of the sample order, which pads
+/// the pairs into as many dimensions as the renderer asks for.
+pub struct SobolSampler {
+    seed: u64,
+    state: SampleState,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: SampleState::default() }
+    }
+
+    /// The index of this pixel's sample within the sequence used for `dimension`.
+    fn shuffled_index(&self, hash: u64) -> u32 {
+        nested_uniform_scramble(self.state.index, hash as u32)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next(1);
+        let hash = self.state.hash(self.seed, dimension);
+        let index = self.shuffled_index(hash);
+        to_unit(nested_uniform_scramble(index.reverse_bits(), (hash >> 32) as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.state.next(2);
+        let hash = self.state.hash(self.seed, dimension);
+        let index = self.shuffled_index(hash);
+        let scramble = rng::hash(&[hash]);
+        (
+            to_unit(nested_uniform_scramble(index.reverse_bits(), scramble as u32)),
+            to_unit(nested_uniform_scramble(sobol_second_dimension(index), (scramble >> 32) as u32)),
+        )
+    }
+}
+
+/// The second dimension of the Sobol sequence as 32 fixed-point bits.
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut v = 1u32 << 31;
+    while index != 0 {
+        if index & 1 != 0 { result ^= v; }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+/// Hash-based Owen scrambling: a random permutation of the binary digits of `x` where each
+/// digit's flip depends only on the digits above it, which keeps a (0, 2)-sequence's
+/// stratification intact.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+/// The element at position `index` of a random permutation of `0..n` chosen by `seed`, without
+/// storing the permutation (Kensler, "Correlated Multi-Jittered Sampling").
+fn permutation_element(mut index: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170893d);
+        index ^= seed >> 16;
+        index ^= (index & w) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929eb3f);
+        index ^= seed >> 23;
+        index ^= (index & w) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935fa69);
+        index ^= (index & w) >> 11;
+        index = index.wrapping_mul(0x74dcb303);
+        index ^= (index & w) >> 2;
+        index = index.wrapping_mul(0x9e501cc3);
+        index ^= (index & w) >> 2;
+        index = index.wrapping_mul(0xc860a3df);
+        index &= w;
+        index ^= index >> 5;
+        if index < n { break; }
+    }
+    (index.wrapping_add(seed)) % n
+}
+
+/// The rank-1 lattices generated by the golden ratio in one dimension and the plastic number in
+/// two (Roberts' R1 and R2 sequences), which fill the unit square very evenly for any number of
+/// samples. Each pixel shifts them by interleaved gradient noise, whose values differ as much
+/// as possible between neighboring pixels, so the remaining error looks like fine blue noise
+/// rather than blotches.
+pub struct BlueNoiseSampler {
+    seed: u64,
+    state: SampleState,
+}
+
+impl BlueNoiseSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: SampleState::default() }
+    }
+
+    /// The blue noise shift of this pixel, decorrelated between dimensions by a random offset.
+    fn shift(&self, x: u32, y: u32, dimension: u32) -> f64 {
+        let offset = to_unit(self.state.hash(self.seed, dimension) as u32);
+        (interleaved_gradient_noise(x, y) + offset).fract()
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        const ALPHA: f64 = 0.618_033_988_749_894_9;
+
+        let dimension = self.state.next(1);
+        let shift = self.shift(self.state.x, self.state.y, dimension);
+        (shift + self.state.index as f64 * ALPHA).fract()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        const G: f64 = 1.324_717_957_244_746;
+        const ALPHA: (f64, f64) = (1.0 / G, 1.0 / (G * G));
+
+        let dimension = self.state.next(2);
+        let (x, y) = (self.state.x, self.state.y);
+        let n = self.state.index as f64;
+        (
+            (self.shift(x, y, dimension) + n * ALPHA.0).fract(),
+            (self.shift(y, x, dimension + 1) + n * ALPHA.1).fract(),
+        )
+    }
+}
+
+/// Jimenez's interleaved gradient noise, a screen-space pattern in [0, 1).
+fn interleaved_gradient_noise(x: u32, y: u32) -> f64 {
+    (52.982_918_9 * (0.067_110_56 * x as f64 + 0.005_837_15 * y as f64).fract()).fract()
+}
+
+/// A point on the unit disk, mapping concentric squares to concentric circles so that the
+/// stratification of `u` is preserved.
+pub fn sample_concentric_disk(u: (f64, f64)) -> Vec3 {
+    let (a, b) = (2.0 * u.0 - 1.0, 2.0 * u.1 - 1.0);
+    if a == 0.0 && b == 0.0 { return Vec3::zero(); }
+
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+/// A uniformly distributed unit vector.
+pub fn sample_uniform_sphere(u: (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// A direction scattered by the Henyey-Greenstein phase function from light travelling along +z.
+/// Positive `g` favors carrying on forwards, negative `g` turning back.
+pub fn sample_henyey_greenstein(u: (f64, f64), g: f64) -> Vec3 {
+    let z = if g.abs() < 1e-3 {
+        1.0 - 2.0 * u.0
+    } else {
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u.0);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    };
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// A direction in the hemisphere around +z, with density proportional to the cosine of its angle
+/// from z.
+pub fn sample_cosine_hemisphere(u: (f64, f64)) -> Vec3 {
+    let d = sample_concentric_disk(u);
+    let z = (1.0 - d.x * d.x - d.y * d.y).max(0.0).sqrt();
+    Vec3::new(d.x, d.y, z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [SamplerKind; 5] = [
+        SamplerKind::Independent,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+        SamplerKind::BlueNoise,
+    ];
+
+    /// The first few dimensions of every sample of one pixel.
+    fn pixel_samples(kind: SamplerKind, spp: u32, x: u32) -> Vec<[f64; 5]> {
+        let mut sampler = kind.create(9, spp);
+        (0..spp)
+            .map(|index| {
+                sampler.start_sample(x, 3, index);
+                let a = sampler.get_1d();
+                let (b, c) = sampler.get_2d();
+                let (d, e) = sampler.get_2d();
+                [a, b, c, d, e]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn values_lie_in_the_unit_interval() {
+        for kind in KINDS {
+            for sample in pixel_samples(kind, 64, 0) {
+                assert!(sample.iter().all(|v| (0.0..1.0).contains(v)), "{kind:?}: {sample:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn samples_depend_only_on_pixel_and_index() {
+        for kind in KINDS {
+            assert_eq!(pixel_samples(kind, 16, 5), pixel_samples(kind, 16, 5), "{kind:?}");
+            assert_ne!(pixel_samples(kind, 16, 5), pixel_samples(kind, 16, 6), "{kind:?}");
+
+            // Restarting a sample replays it, whatever was drawn in between.
+            let mut sampler = kind.create(9, 16);
+            sampler.start_sample(1, 1, 4);
+            let first = sampler.get_2d();
+            sampler.get_1d();
+            sampler.start_sample(1, 1, 4);
+            assert_eq!(sampler.get_2d(), first, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn stratified_kinds_put_one_sample_in_each_stratum() {
+        for kind in [SamplerKind::Stratified, SamplerKind::Sobol] {
+            let samples = pixel_samples(kind, 16, 0);
+            let mut strata: Vec<_> = samples.iter().map(|s| (s[0] * 16.0) as usize).collect();
+            strata.sort();
+            assert_eq!(strata, (0..16).collect::<Vec<_>>(), "{kind:?}");
+
+            let mut cells: Vec<_> = samples.iter()
+                .map(|s| (s[1] * 4.0) as usize + 4 * (s[2] * 4.0) as usize)
+                .collect();
+            cells.sort();
+            assert_eq!(cells, (0..16).collect::<Vec<_>>(), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn warps_stay_on_their_domains() {
+        for u in [(0.0, 0.0), (0.3, 0.9), (0.999, 0.5)] {
+            assert!(sample_concentric_disk(u).len() <= 1.0);
+            assert!((sample_uniform_sphere(u).len() - 1.0).abs() < 1e-12);
+            let v = sample_cosine_hemisphere(u);
+            assert!(v.z >= 0.0 && (v.len() - 1.0).abs() < 1e-12);
+        }
+    }
+}
\ No newline at end of file
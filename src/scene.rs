@@ -0,0 +1,1058 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use vec3::cross;
+
+use crate::prelude::*;
+use crate::animation::{Animated, AnimatedTransform, Interpolation, Keyframe};
+use crate::bvh::{BvhNode, SplitMethod};
+use crate::disk::Disk;
+use crate::grid::DensityGrid;
+use crate::instance::Instance;
+use crate::camera::{MisHeuristic, ShutterCurve};
+use crate::lights::LightList;
+use crate::material::{Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Metal};
+use crate::medium::{ConstantMedium, GridMedium};
+use crate::obj::{load_obj, ObjOptions};
+use crate::quad::{make_box, Quad};
+use crate::sampler::SamplerKind;
+use crate::sphere::Sphere;
+use crate::texture::{
+    CheckerTexture, ImageTexture, NoiseKind, NoiseTexture, SolidColor, Texture, UvCheckerTexture,
+};
+use crate::transform::{Mat4, Transform, Transformed};
+use crate::triangle::Triangle;
+
+/// A world together with the camera that views it.
+pub struct Scene {
+    pub camera: Camera,
+    pub world: HittableList,
+    /// Emissive objects that diffuse surfaces sample directly. Each is also part of `world`.
+    pub lights: LightList,
+}
+
+/// Top level of a TOML scene file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub camera: CameraDescription,
+    #[serde(default)]
+    pub background: BackgroundDescription,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub textures: BTreeMap<String, TextureDescription>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub materials: BTreeMap<String, MaterialDescription>,
+    /// Named groups of objects that `instance` objects place into the world. Each group is
+    /// built once however many instances use it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prototypes: BTreeMap<String, Vec<ObjectDescription>>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraDescription {
+    pub aspect_ratio: f64,
+    pub image_width: u32,
+    pub samples_per_pixel: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adaptive_threshold: Option<f64>,
+    pub min_samples_per_pixel: u32,
+    pub max_depth: i32,
+    pub roulette_depth: u32,
+    pub vfov: f64,
+    pub look_from: [f64; 3],
+    pub look_at: [f64; 3],
+    pub vup: [f64; 3],
+    pub defocus_angle: f64,
+    pub focus_dist: f64,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    /// `[t, openness]` points of the shutter curve; fully open throughout when absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shutter_curve: Option<Vec<[f64; 2]>>,
+    /// Moves the camera over the exposure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub motion: Option<MotionDescription>,
+    /// Seeds the pixel samples, so the same scene and seed render the same image.
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    pub mis: MisHeuristic,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: 16.0 / 9.0,
+            image_width: 400,
+            samples_per_pixel: 100,
+            adaptive_threshold: None,
+            min_samples_per_pixel: 16,
+            max_depth: 50,
+            roulette_depth: 3,
+            vfov: 90.0,
+            look_from: [0.0, 0.0, 0.0],
+            look_at: [0.0, 0.0, -1.0],
+            vup: [0.0, 1.0, 0.0],
+            defocus_angle: 0.0,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            shutter_curve: None,
+            motion: None,
+            seed: 0,
+            sampler: SamplerKind::default(),
+            mis: MisHeuristic::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BackgroundDescription {
+    #[default]
+    Sky,
+    Solid { color: [f64; 3] },
+    Gradient { bottom: [f64; 3], top: [f64; 3] },
+}
+
+/// Either an inline color or the name of an entry in `[textures]`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TextureRef {
+    Color([f64; 3]),
+    Named(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureDescription {
+    Solid { color: [f64; 3] },
+    Checker { scale: f64, even: TextureRef, odd: TextureRef },
+    UvChecker { u_repeats: f64, v_repeats: f64, even: TextureRef, odd: TextureRef },
+    Image { path: PathBuf },
+    Noise {
+        scale: f64,
+        kind: NoiseKind,
+        /// Seeds the random lattice, so the same seed always gives the same pattern.
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDescription {
+    Lambertian { albedo: TextureRef },
+    Metal { albedo: TextureRef, fuzz: f64 },
+    Dielectric { refraction_index: f64 },
+    DiffuseLight { emit: TextureRef },
+    /// The phase function of a medium, scattering evenly in every direction.
+    Isotropic { albedo: TextureRef },
+    /// The phase function of a medium, favoring forward scattering for positive `g` and back
+    /// scattering for negative `g`.
+    HenyeyGreenstein { albedo: TextureRef, g: f64 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObjectDescription {
+    /// A sphere, moving linearly from `center` at time 0 to `center2` at time 1 when `center2`
+    /// is given.
+    Sphere {
+        center: [f64; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        center2: Option<[f64; 3]>,
+        radius: f64,
+        material: String,
+    },
+    Quad { q: [f64; 3], u: [f64; 3], v: [f64; 3], material: String },
+    Triangle {
+        vertices: [[f64; 3]; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normals: Option<[[f64; 3]; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uvs: Option<[[f64; 2]; 3]>,
+        material: String,
+    },
+    Disk { center: [f64; 3], normal: [f64; 3], radius: f64, material: String },
+    Box { a: [f64; 3], b: [f64; 3], material: String },
+    /// A Wavefront OBJ file. `material` is used for faces without an MTL material.
+    Mesh {
+        path: PathBuf,
+        material: String,
+        #[serde(default)]
+        smooth_normals: bool,
+    },
+    /// Several objects treated as one, such as the sides of a box moved by one transform.
+    Group { objects: Vec<ObjectDescription> },
+    /// `object` moved into place by the steps of `transform`, applied in order.
+    Transformed { transform: Vec<TransformDescription>, object: Box<ObjectDescription> },
+    /// A medium of uniform `density` filling the closed surface formed by the `boundary`
+    /// objects, scattering light according to `material`, typically an isotropic one.
+    ConstantMedium { boundary: Vec<ObjectDescription>, density: f64, material: String },
+    /// A medium filling the `boundary` objects, with extinction `density` times the values of
+    /// `grid`. The grid is stretched over `bounds`, given as its minimum and maximum corners, or
+    /// over the boundary's bounding box.
+    GridMedium {
+        boundary: Vec<ObjectDescription>,
+        grid: GridDescription,
+        density: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bounds: Option<[[f64; 3]; 2]>,
+        material: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        emission: Option<EmissionDescription>,
+    },
+    /// `object` moved over time by keyframes.
+    Animated { motion: MotionDescription, object: Box<ObjectDescription> },
+    /// A placement of the named entry of `[prototypes]`, optionally drawn entirely in
+    /// `material` instead of the prototype's own materials.
+    Instance {
+        prototype: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        transform: Vec<TransformDescription>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+    },
+}
+
+/// Where the values of a medium's voxel grid come from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum GridDescription {
+    /// A NumPy `.npy` array indexed `[z][y][x]`, or raw little-endian 32-bit floats with x
+    /// varying fastest, in which case `size` must be given as `[x, y, z]`.
+    File {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<[usize; 3]>,
+    },
+    /// A procedural cloud of Perlin turbulence with frequency `scale`.
+    Noise {
+        size: [usize; 3],
+        scale: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+/// The glow of a grid medium: `color` times the values of `grid`, or of the density grid when
+/// no grid is given.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmissionDescription {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grid: Option<GridDescription>,
+    pub color: [f64; 3],
+}
+
+/// One step of an object's placement.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformDescription {
+    Translate([f64; 3]),
+    /// Counterclockwise rotation around `axis`, in degrees.
+    Rotate { axis: [f64; 3], degrees: f64 },
+    Scale([f64; 3]),
+    /// A full affine matrix, row by row.
+    Matrix([[f64; 4]; 4]),
+}
+
+/// Keyframes of a moving object or camera.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MotionDescription {
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    pub keyframes: Vec<KeyframeDescription>,
+}
+
+/// The scale, then rotation in degrees around x, y and z, then translation at `time`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyframeDescription {
+    pub time: f64,
+    #[serde(default)]
+    pub translate: [f64; 3],
+    #[serde(default)]
+    pub rotate: [f64; 3],
+    #[serde(default = "unit_scale")]
+    pub scale: [f64; 3],
+}
+
+fn unit_scale() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+/// A scene file problem, located by the dotted key path of the offending entry.
+#[derive(Debug)]
+pub struct SceneError {
+    pub file: PathBuf,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "{}: {}", self.file.display(), self.message)
+        } else {
+            write!(f, "{}: {}: {}", self.file.display(), self.key, self.message)
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+/// Reads and builds a TOML scene file.
+pub fn load_scene(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let error = |key: String, message: String| SceneError {
+        file: path.to_path_buf(),
+        key,
+        message,
+    };
+
+    let text = fs::read_to_string(path).map_err(|e| error(String::new(), e.to_string()))?;
+    let description: SceneDescription =
+        serde_path_to_error::deserialize(toml::Deserializer::new(&text))
+            .map_err(|e| {
+                let key = e.path().to_string();
+                let inner = e.into_inner();
+                let message = match inner.span() {
+                    Some(span) => {
+                        let line = text[..span.start].lines().count().max(1);
+                        format!("{} (line {line})", inner.message())
+                    },
+                    None => inner.message().to_string(),
+                };
+                error(if key == "." { String::new() } else { key }, message)
+            })?;
+
+    let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    description.build(&base_dir).map_err(|(key, message)| error(key, message))
+}
+
+type BuildResult<T> = Result<T, (String, String)>;
+
+fn vec3(v: [f64; 3]) -> Vec3 {
+    Vec3::from(&v)
+}
+
+fn is_positive(value: f64) -> bool {
+    value > 0.0 && value.is_finite()
+}
+
+/// Whether the edges `u` and `v` enclose some area, rather than being zero or parallel.
+fn spans_area(u: Vec3, v: Vec3) -> bool {
+    is_positive(cross(u, v).len_squared())
+}
+
+impl SceneDescription {
+    /// Builds the scene, resolving relative file paths against `base_dir`. Errors carry the key
+    /// path of the offending entry and a message.
+    fn build(&self, base_dir: &Path) -> BuildResult<Scene> {
+        let mut builder = Builder {
+            description: self,
+            base_dir,
+            textures: HashMap::new(),
+            resolving: HashSet::new(),
+            materials: HashMap::new(),
+            prototypes: HashMap::new(),
+        };
+
+        let camera = builder.camera()?;
+        let mut world = HittableList::new();
+        let mut lights = LightList::new();
+        for (idx, object) in self.objects.iter().enumerate() {
+            let key = format!("objects[{idx}]");
+            let built = builder.object(object, &key)?;
+            match self.light_radiance(object) {
+                Some(radiance) => {
+                    // The world and the light list share the one object.
+                    let light: Arc<dyn Hittable> = built.into();
+                    world.objects.push(Box::new(light.clone()));
+                    lights.push(light, radiance);
+                },
+                None => world.objects.push(built),
+            }
+        }
+
+        Ok(Scene { camera, world, lights })
+    }
+
+    /// The radiance of `object` if it is a surface with a diffuse light material, used to weigh
+    /// it among the lights. Textured emitters count as white. Meshes are never sampled as lights.
+    fn light_radiance(&self, object: &ObjectDescription) -> Option<Color> {
+        let Some(MaterialDescription::DiffuseLight { emit }) =
+            self.materials.get(self.surface_material(object)?)
+        else {
+            return None;
+        };
+
+        let color = match emit {
+            TextureRef::Color(color) => Some(*color),
+            TextureRef::Named(name) => match self.textures.get(name) {
+                Some(TextureDescription::Solid { color }) => Some(*color),
+                _ => None,
+            },
+        };
+        Some(color.map_or(Color::new(1.0, 1.0, 1.0), vec3))
+    }
+
+    /// The material of `object` if it is a single surface that can be sampled as a light.
+    /// Groups qualify when all their objects are surfaces of one material, and instances when
+    /// their prototype is one such surface.
+    fn surface_material<'a>(&'a self, object: &'a ObjectDescription) -> Option<&'a String> {
+        match object {
+            ObjectDescription::Sphere { material, .. }
+            | ObjectDescription::Quad { material, .. }
+            | ObjectDescription::Triangle { material, .. }
+            | ObjectDescription::Disk { material, .. }
+            | ObjectDescription::Box { material, .. } => Some(material),
+            ObjectDescription::Mesh { .. } => None,
+            ObjectDescription::Group { objects } => {
+                let (first, rest) = objects.split_first()?;
+                let material = self.surface_material(first)?;
+                rest.iter().all(|object| self.surface_material(object) == Some(material))
+                    .then_some(material)
+            },
+            ObjectDescription::Transformed { object, .. } => self.surface_material(object),
+            ObjectDescription::Animated { .. }
+            | ObjectDescription::ConstantMedium { .. }
+            | ObjectDescription::GridMedium { .. } => None,
+            ObjectDescription::Instance { prototype, material, .. } => {
+                let [object] = self.prototypes.get(prototype)?.as_slice() else { return None; };
+                let own = self.surface_material(object)?;
+                Some(material.as_ref().unwrap_or(own))
+            },
+        }
+    }
+}
+
+struct Builder<'a> {
+    description: &'a SceneDescription,
+    base_dir: &'a Path,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    resolving: HashSet<String>,
+    materials: HashMap<String, Arc<dyn Material>>,
+    /// Built prototypes, or `None` while one is being built, to catch prototypes that
+    /// instance themselves.
+    prototypes: HashMap<String, Option<Arc<dyn Hittable>>>,
+}
+
+impl Builder<'_> {
+    fn camera(&self) -> BuildResult<Camera> {
+        let c = &self.description.camera;
+        let invalid = |field: &str, value: &dyn fmt::Debug, reason: &str| {
+            Err((format!("camera.{field}"), format!("{value:?} {reason}")))
+        };
+
+        if !is_positive(c.aspect_ratio) {
+            return invalid("aspect_ratio", &c.aspect_ratio, "must be positive");
+        }
+        if c.image_width == 0 {
+            return invalid("image_width", &c.image_width, "must be positive");
+        }
+        if c.samples_per_pixel == 0 {
+            return invalid("samples_per_pixel", &c.samples_per_pixel, "must be positive");
+        }
+        if c.max_depth <= 0 {
+            return invalid("max_depth", &c.max_depth, "must be positive");
+        }
+        if let Some(threshold) = c.adaptive_threshold {
+            if !is_positive(threshold) {
+                return invalid("adaptive_threshold", &threshold, "must be positive");
+            }
+        }
+        if !(c.vfov > 0.0 && c.vfov < 180.0) {
+            return invalid("vfov", &c.vfov, "must be between 0 and 180 degrees");
+        }
+        if c.look_from == c.look_at {
+            return invalid("look_at", &c.look_at, "must differ from look_from");
+        }
+        if !is_positive(c.focus_dist) {
+            return invalid("focus_dist", &c.focus_dist, "must be positive");
+        }
+        for (field, time) in [("shutter_open", c.shutter_open), ("shutter_close", c.shutter_close)] {
+            if !(0.0..=1.0).contains(&time) {
+                return invalid(field, &time, "must be between 0 and 1");
+            }
+        }
+        if c.shutter_close < c.shutter_open {
+            return invalid("shutter_close", &c.shutter_close, "must not be before shutter_open");
+        }
+        let shutter_curve = match &c.shutter_curve {
+            Some(points) => {
+                ShutterCurve::new(points.iter().map(|&[t, openness]| (t, openness)).collect())
+                    .map_err(|message| ("camera.shutter_curve".to_string(), message))?
+            },
+            None => ShutterCurve::default(),
+        };
+        let motion = c.motion.as_ref()
+            .map(|motion| Self::motion(motion, "camera.motion"))
+            .transpose()?;
+
+        let mut camera = Camera::new(
+            c.aspect_ratio,
+            c.image_width,
+            c.samples_per_pixel,
+            c.max_depth,
+            c.vfov,
+            vec3(c.look_from),
+            vec3(c.look_at),
+            vec3(c.vup),
+            c.defocus_angle,
+            c.focus_dist,
+        );
+        camera.roulette_depth = c.roulette_depth;
+        camera.adaptive_threshold = c.adaptive_threshold;
+        camera.min_samples_per_pixel = c.min_samples_per_pixel;
+        camera.shutter_open = c.shutter_open;
+        camera.shutter_close = c.shutter_close;
+        camera.shutter_curve = shutter_curve;
+        camera.motion = motion;
+        camera.seed = c.seed;
+        camera.sampler = c.sampler;
+        camera.mis = c.mis;
+        camera.background = match self.description.background {
+            BackgroundDescription::Sky => Background::sky(),
+            BackgroundDescription::Solid { color } => Background::Solid(vec3(color)),
+            BackgroundDescription::Gradient { bottom, top } => {
+                Background::Gradient { bottom: vec3(bottom), top: vec3(top) }
+            },
+        };
+        Ok(camera)
+    }
+
+    fn texture_ref(&mut self, texture: &TextureRef, key: &str) -> BuildResult<Arc<dyn Texture>> {
+        match texture {
+            TextureRef::Color(color) => Ok(Arc::new(SolidColor::new(vec3(*color)))),
+            TextureRef::Named(name) => self.named_texture(name, key),
+        }
+    }
+
+    fn named_texture(&mut self, name: &str, key: &str) -> BuildResult<Arc<dyn Texture>> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(texture.clone());
+        }
+        let Some(description) = self.description.textures.get(name) else {
+            return Err((key.to_string(), format!("unknown texture \"{name}\"")));
+        };
+        if !self.resolving.insert(name.to_string()) {
+            return Err((key.to_string(), format!("texture \"{name}\" refers to itself")));
+        }
+
+        let key = format!("textures.{name}");
+        let positive = |field: &str, value: f64| -> BuildResult<()> {
+            if is_positive(value) {
+                Ok(())
+            } else {
+                Err((format!("{key}.{field}"), format!("{value} must be positive")))
+            }
+        };
+        let texture: Arc<dyn Texture> = match description {
+            TextureDescription::Solid { color } => Arc::new(SolidColor::new(vec3(*color))),
+            TextureDescription::Checker { scale, even, odd } => {
+                positive("scale", *scale)?;
+                Arc::new(CheckerTexture::new(
+                    *scale,
+                    self.texture_ref(even, &format!("{key}.even"))?,
+                    self.texture_ref(odd, &format!("{key}.odd"))?,
+                ))
+            },
+            TextureDescription::UvChecker { u_repeats, v_repeats, even, odd } => {
+                positive("u_repeats", *u_repeats)?;
+                positive("v_repeats", *v_repeats)?;
+                Arc::new(UvCheckerTexture::new(
+                    *u_repeats,
+                    *v_repeats,
+                    self.texture_ref(even, &format!("{key}.even"))?,
+                    self.texture_ref(odd, &format!("{key}.odd"))?,
+                ))
+            },
+            TextureDescription::Image { path } => {
+                let full_path = self.base_dir.join(path);
+                let image = ImageTexture::load(&full_path).map_err(|e| {
+                    (format!("{key}.path"), format!("\"{}\": {e}", path.display()))
+                })?;
+                Arc::new(image)
+            },
+            TextureDescription::Noise { scale, kind, seed } => {
+                positive("scale", *scale)?;
+                Arc::new(NoiseTexture::new(*scale, *kind, *seed))
+            },
+        };
+
+        self.resolving.remove(name);
+        self.textures.insert(name.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    fn material(&mut self, name: &str, key: &str) -> BuildResult<Arc<dyn Material>> {
+        if let Some(material) = self.materials.get(name) {
+            return Ok(material.clone());
+        }
+        let Some(description) = self.description.materials.get(name) else {
+            return Err((key.to_string(), format!("unknown material \"{name}\"")));
+        };
+
+        let key = format!("materials.{name}");
+        let material: Arc<dyn Material> = match description {
+            MaterialDescription::Lambertian { albedo } => Arc::new(
+                Lambertian::from_texture(self.texture_ref(albedo, &format!("{key}.albedo"))?)
+            ),
+            MaterialDescription::Metal { albedo, fuzz } => {
+                if !(*fuzz >= 0.0 && fuzz.is_finite()) {
+                    return Err((format!("{key}.fuzz"), format!("{fuzz} must not be negative")));
+                }
+                Arc::new(Metal::from_texture(
+                    self.texture_ref(albedo, &format!("{key}.albedo"))?,
+                    *fuzz,
+                ))
+            },
+            MaterialDescription::Dielectric { refraction_index } => {
+                if !is_positive(*refraction_index) {
+                    return Err((
+                        format!("{key}.refraction_index"),
+                        format!("{refraction_index} must be positive"),
+                    ));
+                }
+                Arc::new(Dielectric::new(*refraction_index))
+            },
+            MaterialDescription::DiffuseLight { emit } => Arc::new(
+                DiffuseLight::from_texture(self.texture_ref(emit, &format!("{key}.emit"))?)
+            ),
+            MaterialDescription::Isotropic { albedo } => Arc::new(
+                Isotropic::from_texture(self.texture_ref(albedo, &format!("{key}.albedo"))?)
+            ),
+            MaterialDescription::HenyeyGreenstein { albedo, g } => {
+                if g.is_nan() || g.abs() >= 1.0 {
+                    return Err((format!("{key}.g"), format!("{g} must be between -1 and 1")));
+                }
+                Arc::new(HenyeyGreenstein::from_texture(
+                    self.texture_ref(albedo, &format!("{key}.albedo"))?,
+                    *g,
+                ))
+            },
+        };
+
+        self.materials.insert(name.to_string(), material.clone());
+        Ok(material)
+    }
+
+    fn object(&mut self, object: &ObjectDescription, key: &str) -> BuildResult<Box<dyn Hittable>> {
+        let positive = |field: &str, value: f64| -> BuildResult<()> {
+            if is_positive(value) {
+                Ok(())
+            } else {
+                Err((format!("{key}.{field}"), format!("{value} must be positive")))
+            }
+        };
+        let material_key = format!("{key}.material");
+
+        let object: Box<dyn Hittable> = match object {
+            ObjectDescription::Sphere { center, center2, radius, material } => {
+                positive("radius", *radius)?;
+                let mat = self.material(material, &material_key)?;
+                match center2 {
+                    Some(center2) => Box::new(
+                        Sphere::moving(vec3(*center), vec3(*center2), *radius, mat)
+                    ),
+                    None => Box::new(Sphere::stationary(vec3(*center), *radius, mat)),
+                }
+            },
+            ObjectDescription::Quad { q, u, v, material } => {
+                if !spans_area(vec3(*u), vec3(*v)) {
+                    return Err((
+                        format!("{key}.v"),
+                        format!("{v:?} must not be zero or parallel to u = {u:?}"),
+                    ));
+                }
+                let mat = self.material(material, &material_key)?;
+                Box::new(Quad::new(vec3(*q), vec3(*u), vec3(*v), mat))
+            },
+            ObjectDescription::Triangle { vertices, normals, uvs, material } => {
+                let [a, b, c] = vertices.map(vec3);
+                if !spans_area(b - a, c - a) {
+                    return Err((
+                        format!("{key}.vertices"),
+                        format!("{vertices:?} must not lie on one line"),
+                    ));
+                }
+                let mat = self.material(material, &material_key)?;
+                Box::new(Triangle::with_attributes(
+                    vertices.map(vec3),
+                    normals.map(|ns| ns.map(vec3)),
+                    uvs.map(|ts| ts.map(|[u, v]| (u, v))),
+                    mat,
+                ))
+            },
+            ObjectDescription::Disk { center, normal, radius, material } => {
+                positive("radius", *radius)?;
+                let mat = self.material(material, &material_key)?;
+                Box::new(Disk::new(vec3(*center), vec3(*normal), *radius, mat))
+            },
+            ObjectDescription::Box { a, b, material } => {
+                let mat = self.material(material, &material_key)?;
+                Box::new(make_box(vec3(*a), vec3(*b), mat))
+            },
+            ObjectDescription::Mesh { path, material, smooth_normals } => {
+                let options = ObjOptions {
+                    smooth_normals: *smooth_normals,
+                    default_material: self.material(material, &material_key)?,
+                };
+                let mesh = load_obj(self.base_dir.join(path), &options)
+                    .map_err(|e| (format!("{key}.path"), e.to_string()))?;
+                Box::new(mesh)
+            },
+            ObjectDescription::Group { objects } => {
+                let mut list = HittableList::new();
+                for (idx, object) in objects.iter().enumerate() {
+                    list.objects.push(self.object(object, &format!("{key}.objects[{idx}]"))?);
+                }
+                Box::new(list)
+            },
+            ObjectDescription::Transformed { transform, object } => {
+                let transform = Self::transform(transform, &format!("{key}.transform"))?;
+                let object = self.object(object, &format!("{key}.object"))?;
+                Box::new(Transformed::new(object, transform))
+            },
+            ObjectDescription::ConstantMedium { boundary, density, material } => {
+                positive("density", *density)?;
+                let boundary = self.boundary(boundary, key)?;
+                let mat = self.material(material, &material_key)?;
+                Box::new(ConstantMedium::new(boundary, *density, mat))
+            },
+            ObjectDescription::GridMedium { boundary, grid, density, bounds, material, emission } => {
+                positive("density", *density)?;
+                let boundary = self.boundary(boundary, key)?;
+                let grid = Arc::new(self.grid(grid, &format!("{key}.grid"))?);
+                let mat = self.material(material, &material_key)?;
+                let mut medium = GridMedium::new(boundary, grid.clone(), *density, mat);
+                if let Some([min, max]) = bounds {
+                    if (0..3).any(|axis| min[axis] >= max[axis]) {
+                        return Err((
+                            format!("{key}.bounds"),
+                            format!("minimum {min:?} must be below maximum {max:?} on every axis"),
+                        ));
+                    }
+                    medium = medium.with_bounds(Aabb::from_points(vec3(*min), vec3(*max)));
+                }
+                if let Some(emission) = emission {
+                    let emission_grid = match &emission.grid {
+                        Some(description) => {
+                            Arc::new(self.grid(description, &format!("{key}.emission.grid"))?)
+                        },
+                        None => grid,
+                    };
+                    medium = medium.with_emission(emission_grid, vec3(emission.color));
+                }
+                Box::new(medium)
+            },
+            ObjectDescription::Animated { motion, object } => {
+                let motion = Self::motion(motion, &format!("{key}.motion"))?;
+                let object = self.object(object, &format!("{key}.object"))?;
+                Box::new(Animated::new(object, motion))
+            },
+            ObjectDescription::Instance { prototype, transform, material } => {
+                let transform = Self::transform(transform, &format!("{key}.transform"))?;
+                let prototype = self.prototype(prototype, &format!("{key}.prototype"))?;
+                let instance = Instance::new(prototype, transform);
+                match material {
+                    Some(material) => Box::new(instance.with_material(
+                        self.material(material, &material_key)?
+                    )),
+                    None => Box::new(instance),
+                }
+            },
+        };
+        Ok(object)
+    }
+
+    /// The objects enclosing a medium, of which there must be at least one.
+    fn boundary(&mut self, objects: &[ObjectDescription], key: &str) -> BuildResult<HittableList> {
+        if objects.is_empty() {
+            return Err((format!("{key}.boundary"), "needs at least one object".to_string()));
+        }
+        let mut list = HittableList::new();
+        for (idx, object) in objects.iter().enumerate() {
+            list.objects.push(self.object(object, &format!("{key}.boundary[{idx}]"))?);
+        }
+        Ok(list)
+    }
+
+    /// Loads or generates a voxel grid, resolving file paths against the scene's directory.
+    fn grid(&self, grid: &GridDescription, key: &str) -> BuildResult<DensityGrid> {
+        match grid {
+            GridDescription::File { path, size } => {
+                DensityGrid::load(self.base_dir.join(path), *size)
+                    .map_err(|e| (format!("{key}.path"), format!("\"{}\": {e}", path.display())))
+            },
+            GridDescription::Noise { size, scale, seed } => {
+                if size.contains(&0) {
+                    return Err((format!("{key}.size"), format!("{size:?} must be positive on every axis")));
+                }
+                if !scale.is_finite() {
+                    return Err((format!("{key}.scale"), format!("{scale} must be finite")));
+                }
+                Ok(DensityGrid::noise(*size, *scale, *seed))
+            },
+        }
+    }
+
+    /// Checks that keyframe times increase and no scale is zero.
+    fn motion(motion: &MotionDescription, key: &str) -> BuildResult<AnimatedTransform> {
+        if motion.keyframes.is_empty() {
+            return Err((format!("{key}.keyframes"), "needs at least one keyframe".to_string()));
+        }
+
+        let mut keyframes = Vec::new();
+        for (idx, k) in motion.keyframes.iter().enumerate() {
+            let key = format!("{key}.keyframes[{idx}]");
+            if keyframes.last().is_some_and(|last: &Keyframe| k.time <= last.time) {
+                return Err((format!("{key}.time"), format!("{} must be after the previous keyframe", k.time)));
+            }
+            if k.scale.iter().any(|&s| s == 0.0 || !s.is_finite()) {
+                return Err((format!("{key}.scale"), format!("{:?} must be nonzero on every axis", k.scale)));
+            }
+            keyframes.push(
+                Keyframe::at(k.time)
+                    .translate(vec3(k.translate))
+                    .rotate(vec3(k.rotate))
+                    .scale(vec3(k.scale))
+            );
+        }
+        Ok(AnimatedTransform::new(keyframes, motion.interpolation))
+    }
+
+    /// Builds the named prototype on first use. Prototypes with several objects get their own
+    /// BVH, so each instance is one node in the world's hierarchy.
+    fn prototype(&mut self, name: &str, key: &str) -> BuildResult<Arc<dyn Hittable>> {
+        match self.prototypes.get(name) {
+            Some(Some(prototype)) => return Ok(prototype.clone()),
+            Some(None) => {
+                return Err((key.to_string(), format!("prototype \"{name}\" instances itself")));
+            },
+            None => {},
+        }
+        let Some(objects) = self.description.prototypes.get(name) else {
+            return Err((key.to_string(), format!("unknown prototype \"{name}\"")));
+        };
+        if objects.is_empty() {
+            return Err((key.to_string(), format!("prototype \"{name}\" has no objects")));
+        }
+
+        self.prototypes.insert(name.to_string(), None);
+        let mut list = HittableList::new();
+        for (idx, object) in objects.iter().enumerate() {
+            list.objects.push(self.object(object, &format!("prototypes.{name}[{idx}]"))?);
+        }
+        let prototype: Arc<dyn Hittable> = match list.objects.len() {
+            1 => Arc::from(list.objects.pop().unwrap()),
+            _ => Arc::new(BvhNode::new(list, SplitMethod::Sah)),
+        };
+
+        self.prototypes.insert(name.to_string(), Some(prototype.clone()));
+        Ok(prototype)
+    }
+
+    /// Composes the steps of a `transform` list, rejecting any that can't be undone.
+    fn transform(steps: &[TransformDescription], key: &str) -> BuildResult<Transform> {
+        steps.iter().enumerate().try_fold(Transform::default(), |transform, (idx, step)| {
+            let invalid = |message: String| Err((format!("{key}[{idx}]"), message));
+            let step = match step {
+                TransformDescription::Translate(offset) => Transform::translate(vec3(*offset)),
+                TransformDescription::Rotate { axis, degrees } => {
+                    if vec3(*axis).near_zero() {
+                        return invalid(format!("rotation axis {axis:?} must not be zero"));
+                    }
+                    Transform::rotate(vec3(*axis), *degrees)
+                },
+                TransformDescription::Scale(factors) => {
+                    if factors.iter().any(|&s| s == 0.0 || !s.is_finite()) {
+                        return invalid(format!("scale {factors:?} must be nonzero on every axis"));
+                    }
+                    Transform::scale(vec3(*factors))
+                },
+                TransformDescription::Matrix(m) => match Transform::from_matrix(Mat4::new(*m)) {
+                    Some(transform) => transform,
+                    None => return invalid("matrix is not invertible".to_string()),
+                },
+            };
+            Ok(transform.then(step))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(text: &str) -> BuildResult<Scene> {
+        let description: SceneDescription = toml::from_str(text).unwrap();
+        description.build(Path::new(""))
+    }
+
+    fn build_error(text: &str) -> (String, String) {
+        match build(text) {
+            Err(error) => error,
+            Ok(_) => panic!("expected the scene to be rejected"),
+        }
+    }
+
+    const GROUND: &str = r#"
+        [[objects]]
+        type = "sphere"
+        center = [0, -100, 0]
+        radius = 100
+        material = "ground"
+    "#;
+
+    #[test]
+    fn builds_objects_and_collects_lights() {
+        let scene = build(&format!(r#"
+            [materials.ground]
+            type = "lambertian"
+            albedo = [0.5, 0.5, 0.5]
+
+            [materials.lamp]
+            type = "diffuse_light"
+            emit = [4, 4, 4]
+
+            {GROUND}
+
+            [[objects]]
+            type = "quad"
+            q = [0, 2, 0]
+            u = [1, 0, 0]
+            v = [0, 0, 1]
+            material = "lamp"
+        "#)).unwrap_or_else(|(key, message)| panic!("{key}: {message}"));
+        assert_eq!(scene.world.objects.len(), 2);
+        assert_eq!(scene.lights.len(), 1);
+    }
+
+    #[test]
+    fn rejects_a_max_depth_that_would_render_black() {
+        let (key, message) = build_error("[camera]\nmax_depth = 0");
+        assert_eq!(key, "camera.max_depth");
+        assert_eq!(message, "0 must be positive");
+    }
+
+    #[test]
+    fn rejects_shutter_times_outside_zero_to_one() {
+        let (key, message) = build_error("[camera]\nshutter_close = 2");
+        assert_eq!(key, "camera.shutter_close");
+        assert_eq!(message, "2.0 must be between 0 and 1");
+
+        let (key, _) = build_error("[camera]\nshutter_open = 0.75\nshutter_close = 0.5");
+        assert_eq!(key, "camera.shutter_close");
+    }
+
+    #[test]
+    fn rejects_a_zero_checker_scale() {
+        let (key, message) = build_error(&format!(r#"
+            [textures.tiles]
+            type = "checker"
+            scale = 0
+            even = [1, 1, 1]
+            odd = [0, 0, 0]
+
+            [materials.ground]
+            type = "lambertian"
+            albedo = "tiles"
+
+            {GROUND}
+        "#));
+        assert_eq!(key, "textures.tiles.scale");
+        assert_eq!(message, "0 must be positive");
+    }
+
+    #[test]
+    fn rejects_negative_or_nan_fuzz_and_zero_noise_scale() {
+        for fuzz in ["-0.5", "nan"] {
+            let (key, message) = build_error(&format!(r#"
+                [materials.ground]
+                type = "metal"
+                albedo = [0.8, 0.8, 0.8]
+                fuzz = {fuzz}
+
+                {GROUND}
+            "#));
+            assert_eq!(key, "materials.ground.fuzz");
+            assert!(message.ends_with("must not be negative"), "{message}");
+        }
+
+        let (key, _) = build_error(&format!(r#"
+            [textures.marble]
+            type = "noise"
+            scale = 0
+            kind = "marble"
+            seed = 1
+
+            [materials.ground]
+            type = "lambertian"
+            albedo = "marble"
+
+            {GROUND}
+        "#));
+        assert_eq!(key, "textures.marble.scale");
+    }
+
+    #[test]
+    fn rejects_flat_quads_and_triangles() {
+        let material = "[materials.white]\ntype = \"lambertian\"\nalbedo = [1, 1, 1]";
+        let (key, _) = build_error(&format!(r#"
+            {material}
+
+            [[objects]]
+            type = "quad"
+            q = [0, 0, 0]
+            u = [1, 0, 0]
+            v = [-2, 0, 0]
+            material = "white"
+        "#));
+        assert_eq!(key, "objects[0].v");
+
+        let (key, message) = build_error(&format!(r#"
+            {material}
+
+            [[objects]]
+            type = "triangle"
+            vertices = [[0, 0, 0], [1, 1, 1], [2, 2, 2]]
+            material = "white"
+        "#));
+        assert_eq!(key, "objects[0].vertices");
+        assert!(message.ends_with("must not lie on one line"), "{message}");
+    }
+
+    #[test]
+    fn reports_the_key_path_of_bad_references() {
+        let (key, message) = build_error(GROUND);
+        assert_eq!(key, "objects[0].material");
+        assert_eq!(message, "unknown material \"ground\"");
+
+        let (key, _) = build_error(r#"
+            [materials.ground]
+            type = "lambertian"
+            albedo = [0.5, 0.5, 0.5]
+
+            [[objects]]
+            type = "sphere"
+            center = [0, 0, 0]
+            radius = -1
+            material = "ground"
+        "#);
+        assert_eq!(key, "objects[0].radius");
+    }
+}
\ No newline at end of file
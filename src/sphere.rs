@@ -1,22 +1,57 @@
-use std::sync::Arc;
+use std::{f64::consts::PI, sync::Arc};
 
 use crate::prelude::*;
+use crate::onb::Onb;
+use crate::sampler::sample_uniform_sphere;
+use crate::export::{ExportError, SceneExporter};
+use crate::scene::ObjectDescription;
 
 pub struct Sphere {
     center: Ray,
     radius: f64,
     mat: Arc<dyn Material>,
+    bbox: Aabb,
 }
 
 impl Sphere {
     pub fn stationary(static_center: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
         let center = Ray::new(static_center, Vec3::zero(), 0.0);
-        Sphere { center, radius, mat, }
+        let rvec = Vec3::new(radius, radius, radius);
+        let bbox = Aabb::from_points(static_center - rvec, static_center + rvec);
+        Sphere { center, radius, mat, bbox }
     }
 
     pub fn moving(center1: Point3, center2: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
         let center = Ray::new(center1, center2 - center1, 0.0);
-        Sphere { center, radius, mat, }
+        let rvec = Vec3::new(radius, radius, radius);
+        let box1 = Aabb::from_points(center.at(0.0) - rvec, center.at(0.0) + rvec);
+        let box2 = Aabb::from_points(center.at(1.0) - rvec, center.at(1.0) + rvec);
+        let bbox = Aabb::from_boxes(&box1, &box2);
+        Sphere { center, radius, mat, bbox }
+    }
+
+    /// p: a given point on the sphere of radius one, centered at the origin.
+    /// u: returned value [0,1] of angle around the Y axis from X=-1.
+    /// v: returned value [0,1] of angle from Y=-1 to Y=+1.
+    ///     <1 0 0> yields <0.50 0.50>       <-1  0  0> yields <0.00 0.50>
+    ///     <0 1 0> yields <0.50 1.00>       < 0 -1  0> yields <0.50 0.00>
+    ///     <0 0 1> yields <0.25 0.50>       < 0  0 -1> yields <0.75 0.50>
+    fn get_sphere_uv(p: &Point3) -> (f64, f64) {
+        let theta = f64::acos(-p.y);
+        let phi = f64::atan2(-p.z, p.x) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// A direction around +z, uniform over the cone subtended by a sphere of `radius` whose
+    /// center is `distance_squared` away along z.
+    fn random_to_sphere(radius: f64, distance_squared: f64, (r1, r2): (f64, f64)) -> Vec3 {
+        let z = 1.0 + r2 * ((1.0 - radius * radius / distance_squared).sqrt() - 1.0);
+
+        let phi = 2.0 * PI * r1;
+        let x = phi.cos() * (1.0 - z * z).sqrt();
+        let y = phi.sin() * (1.0 - z * z).sqrt();
+
+        Vec3::new(x, y, z)
     }
 }
 
@@ -45,15 +80,91 @@ impl Hittable for Sphere {
             if !ray_t.surrounds(root) { return None;}
         }
 
+        let p = r.at(root);
+        let outward_normal = (p - current_center) / self.radius;
+        let (u, v) = Self::get_sphere_uv(&outward_normal);
+
         let mut rec = HitRecord {
-            p: r.at(root),
+            p,
             normal: Default::default(),
             mat: self.mat.clone(),
             t: root,
+            u,
+            v,
             front_face: Default::default(),
         };
-        let outward_normal = (rec.p - current_center) / self.radius;
         rec.set_face_normal(r, outward_normal);
         Some(rec)
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    /// Samples the cone of directions subtended by the sphere. Moving spheres are sampled at
+    /// their starting position.
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let ray = Ray::new(origin, direction, 0.0);
+        if self.hit(&ray, Interval::new(0.001, f64::INFINITY)).is_none() { return 0.0; }
+
+        let dist_squared = (self.center.at(0.0) - origin).len_squared();
+        let radius_squared = self.radius * self.radius;
+        if dist_squared <= radius_squared { return 1.0 / (4.0 * PI); }
+
+        let cos_theta_max = (1.0 - radius_squared / dist_squared).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let direction = self.center.at(0.0) - origin;
+        let distance_squared = direction.len_squared();
+        if distance_squared <= self.radius * self.radius { return sample_uniform_sphere(sampler.get_2d()); }
+
+        let uvw = Onb::new(direction);
+        uvw.transform(Self::random_to_sphere(self.radius, distance_squared, sampler.get_2d()))
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let center2 = (self.center.direction.len_squared() > 0.0)
+            .then(|| self.center.at(1.0).into());
+        let material = scene.material(&self.mat)?;
+        scene.push_object(ObjectDescription::Sphere {
+            center: self.center.origin.into(),
+            center2,
+            radius: self.radius,
+            material,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::DiffuseLight;
+    use crate::sampler::IndependentSampler;
+
+    #[test]
+    fn sampling_a_sphere_light_covers_its_solid_angle() {
+        let mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
+        let light = Sphere::stationary(Point3::new(0.0, 0.0, -4.0), 1.0, mat);
+
+        // Directions drawn in proportion to the density, weighted by its inverse, measure the
+        // solid angle they cover.
+        let mut sampler = IndependentSampler::new(5);
+        let n = 10_000;
+        let mut measure = 0.0;
+        for _ in 0..n {
+            let direction = light.random(Point3::zero(), &mut sampler);
+            assert!(light.hit(&Ray::new(Point3::zero(), direction, 0.0), Interval::universe()).is_some());
+            measure += 1.0 / light.pdf_value(Point3::zero(), direction);
+        }
+        let solid_angle = 2.0 * PI * (1.0 - f64::sqrt(1.0 - 1.0 / 16.0));
+        assert!((measure / n as f64 - solid_angle).abs() < 1e-6 * solid_angle);
+    }
 }
\ No newline at end of file
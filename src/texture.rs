@@ -0,0 +1,266 @@
+use std::{fs, path::{Path, PathBuf}, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::perlin::Perlin;
+use crate::rng;
+use crate::scene::TextureDescription;
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+
+    /// The scene file description of this texture. Textures that have none can leave this
+    /// out, and exporting a scene using them fails.
+    fn describe(&self, _scene: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Err(ExportError::unsupported("this kind of texture"))
+    }
+}
+
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+
+    pub fn rgb(red: f64, green: f64, blue: f64) -> Self {
+        Self::new(Color::new(red, green, blue))
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.albedo
+    }
+
+    fn describe(&self, _scene: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::Solid { color: self.albedo.into() })
+    }
+}
+
+/// Alternates between two textures in a 3D grid of cubes of side `scale`.
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self { inv_scale: 1.0 / scale, even, odd }
+    }
+
+    pub fn from_colors(scale: f64, c1: Color, c2: Color) -> Self {
+        Self::new(scale, Arc::new(SolidColor::new(c1)), Arc::new(SolidColor::new(c2)))
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let x_integer = (self.inv_scale * p.x).floor() as i64;
+        let y_integer = (self.inv_scale * p.y).floor() as i64;
+        let z_integer = (self.inv_scale * p.z).floor() as i64;
+
+        if (x_integer + y_integer + z_integer) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::Checker {
+            scale: 1.0 / self.inv_scale,
+            even: scene.texture(&self.even)?,
+            odd: scene.texture(&self.odd)?,
+        })
+    }
+}
+
+/// Alternates between two textures in surface space, with `u_repeats` by `v_repeats` squares
+/// across the full [0,1] UV range.
+pub struct UvCheckerTexture {
+    u_repeats: f64,
+    v_repeats: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl UvCheckerTexture {
+    pub fn new(u_repeats: f64, v_repeats: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self { u_repeats, v_repeats, even, odd }
+    }
+
+    pub fn from_colors(u_repeats: f64, v_repeats: f64, c1: Color, c2: Color) -> Self {
+        Self::new(
+            u_repeats,
+            v_repeats,
+            Arc::new(SolidColor::new(c1)),
+            Arc::new(SolidColor::new(c2)),
+        )
+    }
+}
+
+impl Texture for UvCheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let u_integer = (u * self.u_repeats).floor() as i64;
+        let v_integer = (v * self.v_repeats).floor() as i64;
+
+        if (u_integer + v_integer) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::UvChecker {
+            u_repeats: self.u_repeats,
+            v_repeats: self.v_repeats,
+            even: scene.texture(&self.even)?,
+            odd: scene.texture(&self.odd)?,
+        })
+    }
+}
+
+/// Texture backed by a PNG, JPEG or PPM image, addressed by UV coordinates.
+pub struct ImageTexture {
+    path: PathBuf,
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, image::ImageError> {
+        let path = path.as_ref();
+        let img = image::open(path)?.into_rgb8();
+        let (width, height) = (img.width() as usize, img.height() as usize);
+
+        // Stored images are gamma encoded; undo the same gamma 2 transform `write_color` applies
+        // so the texture round-trips through a render unchanged.
+        let pixels = img.pixels()
+            .map(|px| {
+                let [r, g, b] = px.0.map(|c| {
+                    let c = c as f64 / 255.0;
+                    c * c
+                });
+                Color::new(r, g, b)
+            })
+            .collect();
+
+        // Remember where the image came from so exported scenes can find it again.
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        Ok(Self { path, width, height, pixels })
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        // If we have no texture data, then return solid cyan as a debugging aid.
+        if self.height == 0 { return Color::new(0.0, 1.0, 1.0); }
+
+        // Clamp input texture coordinates to [0,1] x [1,0]
+        let u = Interval::new(0.0, 1.0).clamp(u);
+        let v = 1.0 - Interval::new(0.0, 1.0).clamp(v); // Flip V to image coordinates
+
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[j * self.width + i]
+    }
+
+    fn describe(&self, _scene: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::Image { path: self.path.clone() })
+    }
+}
+
+/// How a `NoiseTexture` turns Perlin noise into a gray level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    /// Plain noise, remapped to [0, 1].
+    Smooth,
+    /// Seven octaves of summed noise.
+    Turbulence,
+    /// Sine bands along z, phase-shifted by turbulence.
+    Marble,
+}
+
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+    kind: NoiseKind,
+    seed: u64,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64, kind: NoiseKind, seed: u64) -> Self {
+        Self { noise: Perlin::new(&mut rng::seeded(seed)), scale, kind, seed }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let gray = match self.kind {
+            NoiseKind::Smooth => 0.5 * (1.0 + self.noise.noise(&(self.scale * *p))),
+            NoiseKind::Turbulence => self.noise.turb(&(self.scale * *p), 7),
+            NoiseKind::Marble => 0.5 * (1.0 + f64::sin(self.scale * p.z + 10.0 * self.noise.turb(p, 7))),
+        };
+        Color::new(1.0, 1.0, 1.0) * gray
+    }
+
+    /// Only the parameters are written; the seed regenerates the same noise lattice on load.
+    fn describe(&self, _scene: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::Noise { scale: self.scale, kind: self.kind, seed: self.seed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn is_white(c: Color) -> bool {
+        c.x == 1.0
+    }
+
+    #[test]
+    fn checkers_alternate_across_cube_faces_including_negative_ones() {
+        let checker = CheckerTexture::from_colors(0.5, white(), Color::zero());
+        let at = |x, y, z| is_white(checker.value(0.0, 0.0, &Point3::new(x, y, z)));
+        assert!(at(0.1, 0.1, 0.1));
+        assert!(!at(0.6, 0.1, 0.1));
+        assert!(!at(-0.1, 0.1, 0.1));
+        assert!(at(-0.1, -0.1, 0.1));
+        assert!(at(0.6, 0.6, 0.1));
+    }
+
+    #[test]
+    fn uv_checkers_repeat_the_given_number_of_times() {
+        let checker = UvCheckerTexture::from_colors(4.0, 2.0, white(), Color::zero());
+        let at = |u, v| is_white(checker.value(u, v, &Point3::zero()));
+        assert!(at(0.1, 0.1));
+        assert!(!at(0.3, 0.1));
+        assert!(at(0.6, 0.1));
+        assert!(!at(0.1, 0.6));
+    }
+
+    #[test]
+    fn noise_is_reproducible_and_in_range() {
+        let a = NoiseTexture::new(4.0, NoiseKind::Smooth, 9);
+        let b = NoiseTexture::new(4.0, NoiseKind::Smooth, 9);
+        for i in 0..100 {
+            let p = Point3::new(i as f64 * 0.37, i as f64 * -0.11, 1.3);
+            let value = a.value(0.0, 0.0, &p).x;
+            assert_eq!(value, b.value(0.0, 0.0, &p).x);
+            assert!((0.0..=1.0).contains(&value));
+        }
+    }
+}
\ No newline at end of file
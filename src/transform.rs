@@ -0,0 +1,435 @@
+use std::ops::Mul;
+
+use vec3::cross;
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::scene::{ObjectDescription, TransformDescription};
+
+/// Row-major 4x4 matrix acting on column vectors in homogeneous coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self { m }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self { m }
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting. Returns `None` for singular matrices.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 { return None; }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == col { continue; }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+        Some(Self { m: inv })
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w == 1.0 { Point3::new(x, y, z) } else { Point3::new(x, y, z) / w }
+    }
+
+    /// Applies the linear part only, as befits a direction.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Self { m }
+    }
+}
+
+/// An invertible affine transform, kept together with its inverse so that neither has to be
+/// recomputed per ray.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    matrix: Mat4,
+    inverse: Mat4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self { matrix: Mat4::identity(), inverse: Mat4::identity() }
+    }
+}
+
+impl Transform {
+    /// Returns `None` if `matrix` can't be inverted.
+    pub fn from_matrix(matrix: Mat4) -> Option<Self> {
+        Some(Self { matrix, inverse: matrix.inverse()? })
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        let mut matrix = Mat4::identity();
+        let mut inverse = Mat4::identity();
+        for (i, d) in [offset.x, offset.y, offset.z].into_iter().enumerate() {
+            matrix.m[i][3] = d;
+            inverse.m[i][3] = -d;
+        }
+        Self { matrix, inverse }
+    }
+
+    /// Scales by a separate factor along each axis. None of them may be zero.
+    pub fn scale(factors: Vec3) -> Self {
+        let mut matrix = Mat4::identity();
+        let mut inverse = Mat4::identity();
+        for (i, s) in [factors.x, factors.y, factors.z].into_iter().enumerate() {
+            matrix.m[i][i] = s;
+            inverse.m[i][i] = 1.0 / s;
+        }
+        Self { matrix, inverse }
+    }
+
+    /// Rotates counterclockwise by `degrees` around `axis` (when looking down the axis towards
+    /// the origin), using Rodrigues' formula.
+    pub fn rotate(axis: Vec3, degrees: f64) -> Self {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        let matrix = Mat4::new([
+            [t * a.x * a.x + cos, t * a.x * a.y - sin * a.z, t * a.x * a.z + sin * a.y, 0.0],
+            [t * a.x * a.y + sin * a.z, t * a.y * a.y + cos, t * a.y * a.z - sin * a.x, 0.0],
+            [t * a.x * a.z - sin * a.y, t * a.y * a.z + sin * a.x, t * a.z * a.z + cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        // Rotations are orthogonal, so the inverse is the transpose.
+        Self { matrix, inverse: matrix.transpose() }
+    }
+
+    pub fn rotate_x(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(1.0, 0.0, 0.0), degrees)
+    }
+
+    pub fn rotate_y(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn rotate_z(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 0.0, 1.0), degrees)
+    }
+
+    /// The transform applying `self` first and then `next`.
+    pub fn then(self, next: Transform) -> Self {
+        Self { matrix: next.matrix * self.matrix, inverse: self.inverse * next.inverse }
+    }
+
+    pub fn inverse(self) -> Self {
+        Self { matrix: self.inverse, inverse: self.matrix }
+    }
+
+    pub fn matrix(&self) -> &Mat4 {
+        &self.matrix
+    }
+
+    pub fn point(&self, p: Point3) -> Point3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    /// Transforms a surface normal with the inverse transpose, which keeps it perpendicular to
+    /// the surface under non-uniform scaling. The result is not normalized.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(n)
+    }
+
+    /// Takes a world space ray into the space the transform maps from. The direction is not
+    /// normalized, so hit distances `t` stay the same in both spaces.
+    pub fn inverse_ray(&self, r: &Ray) -> Ray {
+        Ray::new(self.inverse.transform_point(r.origin), self.inverse.transform_vector(r.direction), r.time)
+    }
+
+    /// Intersects the world space ray `r` with `object` placed by this transform.
+    pub fn hit(&self, object: &dyn Hittable, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        object.hit(&self.inverse_ray(r), ray_t).map(|rec| self.hit_record(rec))
+    }
+
+    /// Like `hit`, but only with the surfaces of `object`.
+    pub fn hit_surface(&self, object: &dyn Hittable, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        object.hit_surface(&self.inverse_ray(r), ray_t).map(|rec| self.hit_record(rec))
+    }
+
+    /// Like `hit`, with random decisions drawn from `sampler`.
+    pub fn hit_sampled(
+        &self,
+        object: &dyn Hittable,
+        r: &Ray,
+        ray_t: Interval,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        object.hit_sampled(&self.inverse_ray(r), ray_t, sampler).map(|rec| self.hit_record(rec))
+    }
+
+    /// Takes a hit found in the object's own space out into the world.
+    fn hit_record(&self, mut rec: HitRecord) -> HitRecord {
+        // The object already faced its normal against the ray, and the inverse transpose keeps
+        // which side of the surface a direction is on, so `front_face` carries over.
+        rec.p = self.point(rec.p);
+        rec.normal = self.normal(rec.normal).unit_vector();
+        rec
+    }
+
+    /// The box around the transformed corners of `bbox`.
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        if bbox.is_empty() { return *bbox; }
+
+        let corners = (0..8).map(|i| Point3::new(
+            if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+            if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+            if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+        ));
+        let mut result = Aabb::empty();
+        for corner in corners {
+            let p = self.point(corner);
+            if !(p.x.is_finite() && p.y.is_finite() && p.z.is_finite()) { return Aabb::universe(); }
+            result = Aabb::from_boxes(&result, &Aabb::from_points(p, p));
+        }
+        Aabb::new(result.x, result.y, result.z)
+    }
+
+    /// The factor by which the transform scales lengths, if it scales them the same in every
+    /// direction. Such transforms preserve angles and therefore solid angle densities.
+    pub fn uniform_scale(&self) -> Option<f64> {
+        let m = &self.matrix.m;
+        let columns = [0, 1, 2].map(|j| Vec3::new(m[0][j], m[1][j], m[2][j]));
+        let s2 = columns[0].len_squared();
+        let tolerance = 1e-9 * s2;
+
+        let orthogonal = dot(columns[0], columns[1]).abs() <= tolerance
+            && dot(columns[1], columns[2]).abs() <= tolerance
+            && dot(columns[0], columns[2]).abs() <= tolerance;
+        let equal = (columns[1].len_squared() - s2).abs() <= tolerance
+            && (columns[2].len_squared() - s2).abs() <= tolerance;
+        let affine = m[3] == [0.0, 0.0, 0.0, 1.0];
+        (orthogonal && equal && affine && cross(columns[0], columns[1]).len_squared() > 0.0)
+            .then(|| s2.sqrt())
+    }
+}
+
+/// An object placed in the world by a transform: rays are taken into the object's own space,
+/// intersected there, and the hit point and normal are mapped back out.
+pub struct Transformed<H: Hittable> {
+    object: H,
+    transform: Transform,
+    bbox: Aabb,
+}
+
+impl<H: Hittable> Transformed<H> {
+    pub fn new(object: H, transform: Transform) -> Self {
+        let bbox = transform.bounding_box(&object.bounding_box());
+        Self { object, transform, bbox }
+    }
+
+    pub fn object(&self) -> &H {
+        &self.object
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl<H: Hittable> Hittable for Transformed<H> {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.transform.hit(&self.object, r, ray_t)
+    }
+
+    fn hit_surface(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.transform.hit_surface(&self.object, r, ray_t)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> f64 {
+        self.object.transmittance(&self.transform.inverse_ray(r), ray_t)
+    }
+
+    fn hit_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        self.transform.hit_sampled(&self.object, r, ray_t, sampler)
+    }
+
+    fn transmittance_sampled(&self, r: &Ray, ray_t: Interval, sampler: &mut dyn Sampler) -> f64 {
+        self.object.transmittance_sampled(&self.transform.inverse_ray(r), ray_t, sampler)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    /// Only objects scaled the same in every direction can be sampled as lights, since other
+    /// transforms distort the object's solid angle densities.
+    fn area(&self) -> f64 {
+        match self.transform.uniform_scale() {
+            Some(s) => s * s * self.object.area(),
+            None => 0.0,
+        }
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        if self.transform.uniform_scale().is_none() { return 0.0; }
+
+        let inverse = self.transform.inverse();
+        self.object.pdf_value(inverse.point(origin), inverse.vector(direction))
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        let direction = self.object.random(self.transform.inverse().point(origin), sampler);
+        self.transform.vector(direction)
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let object = scene.capture_one(|scene| self.object.describe(scene))?;
+        scene.push_object(ObjectDescription::Transformed {
+            transform: vec![TransformDescription::Matrix(self.transform.matrix().m)],
+            object: Box::new(object),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::quad::make_box;
+
+    fn assert_near(a: &Mat4, b: &Mat4) {
+        for (row_a, row_b) in a.m.iter().zip(&b.m) {
+            for (x, y) in row_a.iter().zip(row_b) {
+                assert!((x - y).abs() < 1e-12, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let m = Mat4::new([
+            [2.0, 1.0, 0.0, 3.0],
+            [0.0, 0.0, 4.0, -1.0],
+            [1.0, 3.0, 0.0, 0.5],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let inverse = m.inverse().unwrap();
+        assert_near(&(m * inverse), &Mat4::identity());
+        assert_near(&(inverse * m), &Mat4::identity());
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        let mut m = Mat4::identity();
+        m.m[1] = [0.0; 4];
+        assert!(m.inverse().is_none());
+        assert!(Transform::from_matrix(m).is_none());
+    }
+
+    #[test]
+    fn steps_apply_in_order() {
+        let t = Transform::scale(Vec3::new(2.0, 2.0, 2.0))
+            .then(Transform::rotate_y(90.0))
+            .then(Transform::translate(Vec3::new(0.0, 1.0, 0.0)));
+        let p = t.point(Point3::new(1.0, 0.0, 0.0));
+        assert!((p - Point3::new(0.0, 1.0, -2.0)).len() < 1e-12);
+        assert!((t.inverse().point(p) - Point3::new(1.0, 0.0, 0.0)).len() < 1e-12);
+        assert_eq!(t.uniform_scale().map(|s| (s * 1e12).round() / 1e12), Some(2.0));
+        assert!(Transform::scale(Vec3::new(1.0, 2.0, 1.0)).uniform_scale().is_none());
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let t = Transform::scale(Vec3::new(4.0, 1.0, 1.0));
+        let tangent = t.vector(Vec3::new(1.0, -1.0, 0.0));
+        let normal = t.normal(Vec3::new(1.0, 1.0, 0.0));
+        assert!(dot(tangent, normal).abs() < 1e-12);
+    }
+
+    #[test]
+    fn transformed_objects_are_hit_where_they_were_moved() {
+        let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let unit_box = make_box(Point3::zero(), Point3::new(1.0, 1.0, 1.0), mat);
+        let moved = Transformed::new(unit_box, Transform::translate(Vec3::new(10.0, 0.0, 0.0)));
+        assert!((moved.bounding_box().x.min - 10.0).abs() < 1e-3);
+
+        let r = Ray::new(Point3::new(10.5, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = moved.hit(&r, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-12);
+        assert!((rec.p - Point3::new(10.5, 0.5, 1.0)).len() < 1e-12);
+        assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn a_transformed_list_is_exported_as_one_group() {
+        let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let unit_box = make_box(Point3::zero(), Point3::new(1.0, 1.0, 1.0), mat);
+        let moved = Transformed::new(unit_box, Transform::rotate_y(15.0));
+
+        let mut scene = SceneExporter::new();
+        let objects = scene.capture(|scene| moved.describe(scene)).unwrap();
+        let [ObjectDescription::Transformed { object, .. }] = objects.as_slice() else {
+            panic!("expected one transformed object, got {objects:?}");
+        };
+        assert!(matches!(&**object, ObjectDescription::Group { objects } if objects.len() == 6));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use vec3::cross;
+
+use crate::prelude::*;
+use crate::export::{ExportError, SceneExporter};
+use crate::scene::ObjectDescription;
+
+/// Möller–Trumbore ray/triangle intersection. Returns the ray parameter together with the
+/// barycentric weights of vertices `b` and `c`.
+pub(crate) fn intersect(
+    a: Point3,
+    b: Point3,
+    c: Point3,
+    r: &Ray,
+    ray_t: Interval,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let pvec = cross(r.direction, edge2);
+    let det = dot(edge1, pvec);
+
+    // No hit if the ray is parallel to the triangle's plane.
+    if det.abs() < 1e-12 { return None; }
+    let inv_det = 1.0 / det;
+
+    let tvec = r.origin - a;
+    let b1 = dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) { return None; }
+
+    let qvec = cross(tvec, edge1);
+    let b2 = dot(r.direction, qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 { return None; }
+
+    let t = dot(edge2, qvec) * inv_det;
+    if !ray_t.surrounds(t) { return None; }
+
+    Some((t, b1, b2))
+}
+
+/// Fills in the normal and texture coordinates of a triangle hit. The face side is decided by the
+/// geometric normal; interpolated vertex normals, when present, are only used for shading.
+pub(crate) fn shade(
+    rec: &mut HitRecord,
+    r: &Ray,
+    geometric_normal: Vec3,
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    b1: f64,
+    b2: f64,
+) {
+    let b0 = 1.0 - b1 - b2;
+    rec.set_face_normal(r, geometric_normal);
+
+    if let Some([n0, n1, n2]) = normals {
+        let shading_normal = (b0 * n0 + b1 * n1 + b2 * n2).unit_vector();
+        rec.normal = if rec.front_face { shading_normal } else { -shading_normal };
+    }
+
+    (rec.u, rec.v) = match uvs {
+        Some([t0, t1, t2]) => (
+            b0 * t0.0 + b1 * t1.0 + b2 * t2.0,
+            b0 * t0.1 + b1 * t1.1 + b2 * t2.1,
+        ),
+        None => (b1, b2),
+    };
+}
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    normal: Vec3,
+    area: f64,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, mat: Arc<dyn Material>) -> Self {
+        Self::with_attributes([a, b, c], None, None, mat)
+    }
+
+    /// Creates a triangle with optional per-vertex shading normals and texture coordinates.
+    /// Without UVs, the barycentric coordinates of the hit are reported instead.
+    pub fn with_attributes(
+        vertices: [Point3; 3],
+        normals: Option<[Vec3; 3]>,
+        uvs: Option<[(f64, f64); 3]>,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let [a, b, c] = vertices;
+        let n = cross(b - a, c - a);
+        let normal = n.unit_vector();
+        let area = 0.5 * n.len();
+        let bbox = Aabb::from_boxes(&Aabb::from_points(a, b), &Aabb::from_points(b, c));
+        let normals = normals.map(|ns| ns.map(|n| n.unit_vector()));
+        Self { vertices, normals, uvs, normal, area, mat, bbox }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let [a, b, c] = self.vertices;
+        let (t, b1, b2) = intersect(a, b, c, r, ray_t)?;
+
+        let mut rec = HitRecord {
+            p: r.at(t),
+            normal: Default::default(),
+            mat: self.mat.clone(),
+            t,
+            u: 0.0,
+            v: 0.0,
+            front_face: Default::default(),
+        };
+        shade(&mut rec, r, self.normal, self.normals, self.uvs, b1, b2);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn area(&self) -> f64 {
+        self.area
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let [a, b, c] = self.vertices;
+        let ray = Ray::new(origin, direction, 0.0);
+        let Some((t, _, _)) = intersect(a, b, c, &ray, Interval::new(0.001, f64::INFINITY)) else {
+            return 0.0;
+        };
+
+        let distance_squared = t * t * direction.len_squared();
+        let cosine = (dot(direction, self.normal) / direction.len()).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        // Fold points from the far half of the parallelogram back into the triangle.
+        let [a, b, c] = self.vertices;
+        let (mut b1, mut b2) = sampler.get_2d();
+        if b1 + b2 > 1.0 {
+            (b1, b2) = (1.0 - b1, 1.0 - b2);
+        }
+        a + b1 * (b - a) + b2 * (c - a) - origin
+    }
+
+    fn describe(&self, scene: &mut SceneExporter) -> Result<(), ExportError> {
+        let material = scene.material(&self.mat)?;
+        scene.push_object(ObjectDescription::Triangle {
+            vertices: self.vertices.map(Into::into),
+            normals: self.normals.map(|ns| ns.map(Into::into)),
+            uvs: self.uvs.map(|ts| ts.map(|(u, v)| [u, v])),
+            material,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    fn triangle(normals: Option<[Vec3; 3]>, uvs: Option<[(f64, f64); 3]>) -> Triangle {
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let vertices = [Point3::zero(), Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0)];
+        Triangle::with_attributes(vertices, normals, uvs, mat)
+    }
+
+    fn down_at(x: f64, y: f64) -> Ray {
+        Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0)
+    }
+
+    #[test]
+    fn hit_reports_barycentric_coordinates_without_uvs() {
+        let rec = triangle(None, None).hit(&down_at(0.2, 0.3), Interval::new(0.001, 10.0)).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-12);
+        assert!((rec.u - 0.2).abs() < 1e-12 && (rec.v - 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn interpolates_uvs_and_shading_normals() {
+        let uvs = Some([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+        let tilted = Vec3::new(1.0, 0.0, 1.0).unit_vector();
+        let normals = Some([tilted, tilted, tilted]);
+        let rec = triangle(normals, uvs).hit(&down_at(0.5, 0.25), Interval::new(0.001, 10.0)).unwrap();
+        assert!((rec.u - 0.75).abs() < 1e-12 && (rec.v - 0.25).abs() < 1e-12);
+        assert!((rec.normal - tilted).len() < 1e-12);
+    }
+
+    #[test]
+    fn misses_outside_and_at_the_interval_bounds() {
+        let tri = triangle(None, None);
+        assert!(tri.hit(&down_at(0.6, 0.6), Interval::new(0.001, 10.0)).is_none());
+        assert!(tri.hit(&down_at(0.2, 0.2), Interval::new(0.0, 1.0)).is_none());
+        assert!(tri.hit(&down_at(0.2, 0.2), Interval::new(1.0, 2.0)).is_none());
+    }
+
+    #[test]
+    fn random_directions_land_on_the_triangle() {
+        let tri = triangle(None, None);
+        let origin = Point3::new(0.1, 0.1, 1.0);
+        let mut sampler = crate::sampler::SamplerKind::Independent.create(1, 16);
+        sampler.start_sample(0, 0, 0);
+        for _ in 0..100 {
+            let direction = tri.random(origin, sampler.as_mut());
+            assert!(tri.pdf_value(origin, direction) > 0.0);
+        }
+    }
+}
\ No newline at end of file
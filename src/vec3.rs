@@ -1,8 +1,6 @@
 use std::{iter::Sum, ops::{Add, AddAssign, Div, Index, Mul, Neg, Sub}};
 
-use rand::random;
-
-use crate::random_range;
+use crate::prelude::{random, random_range, Rng};
 
 pub type Point3 = Vec3;
 pub type Color = Vec3;
@@ -23,15 +21,15 @@ impl Vec3 {
         Self { x: 0.0, y: 0.0, z: 0.0 }
     }
 
-    pub fn random() -> Self {
-        Self { x: random(), y: random(), z: random() }
+    pub fn random(rng: &mut Rng) -> Self {
+        Self { x: random(rng), y: random(rng), z: random(rng) }
     }
 
-    pub fn random_range(min: f64, max: f64) -> Self {
+    pub fn random_range(rng: &mut Rng, min: f64, max: f64) -> Self {
         Self {
-            x: random_range(min, max),
-            y: random_range(min, max),
-            z: random_range(min, max),
+            x: random_range(rng, min, max),
+            y: random_range(rng, min, max),
+            z: random_range(rng, min, max),
         }
     }
 
@@ -173,6 +171,12 @@ impl From<&[f64; 3]> for Vec3 {
     }
 }
 
+impl From<Vec3> for [f64; 3] {
+    fn from(value: Vec3) -> Self {
+        [value.x, value.y, value.z]
+    }
+}
+
 pub fn dot(a: Vec3, b: Vec3) -> f64 {
     a.x * b.x + a.y * b.y + a.z * b.z
 }
@@ -185,34 +189,6 @@ pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
     }
 }
 
-pub fn random_unit_vector() -> Vec3 {
-    loop {
-        let p = Vec3::random_range(-1.0, 1.0);
-        let lensq = p.len_squared();
-        if 1e-160 < lensq && lensq <= 1.0 {
-            return p / lensq.sqrt();
-        }
-    }
-}
-
-pub fn random_on_hemisphere(normal: &Vec3) -> Vec3 {
-    let on_unit_sphere: Vec3 = random_unit_vector();
-    return if dot(on_unit_sphere, *normal) > 0.0 { // In the same hemisphere as the normal
-        on_unit_sphere
-    } else {
-        -on_unit_sphere
-    }
-}
-
-pub fn random_in_unit_disk() -> Vec3 {
-    loop {
-        let p = Vec3::new(
-            random_range(-1.0, 1.0), random_range(-1.0, 1.0), 0.0
-        );
-        if p.len_squared() < 1.0 { return p; }
-    }
-}
-
 pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
     v - 2.0 * dot(v, n) * n
 }